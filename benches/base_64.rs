@@ -0,0 +1,105 @@
+// Compares the split/stitch path against the lookup table path on the same payload,
+// and the lookup tables on one thread against encode_parallel/decode_parallel.
+// Run with `cargo bench`, it prints the throughput of each and the speedup.
+use std::time::{Duration, Instant};
+use test_shit::base_64_encoding::*;
+
+const PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
+const ROUNDS: u32 = 5;
+
+// average time of a round, after one round to warm up
+fn measure<F: FnMut()>(mut run: F) -> Duration {
+    run();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        run();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn report(
+    name: &str,
+    (before_name, before): (&str, Duration),
+    (after_name, after): (&str, Duration),
+) {
+    let throughput = |time: Duration| PAYLOAD_SIZE as f64 / time.as_secs_f64() / (1024.0 * 1024.0);
+    println!(
+        "{:<7} {:>13} {:>9.1} MiB/s   {:>13} {:>9.1} MiB/s   speedup {:.1}x",
+        name,
+        before_name,
+        throughput(before),
+        after_name,
+        throughput(after),
+        before.as_secs_f64() / after.as_secs_f64()
+    );
+}
+
+fn main() {
+    let data = (0..PAYLOAD_SIZE)
+        .map(|i| (i * 31 % 256) as u8)
+        .collect::<Vec<u8>>();
+    let tables = LookupTables::new(&Classic).unwrap();
+    let encoded = encode(&data);
+
+    let mut out = vec![0; encoded_len(data.len(), Padding::Pad).unwrap()];
+    let split = measure(|| {
+        std::hint::black_box(encode(std::hint::black_box(&data)));
+    });
+    let table = measure(|| {
+        encode_to_slice(&tables, std::hint::black_box(&data), &mut out, Padding::Pad).unwrap();
+    });
+    report("encode", ("split/stitch", split), ("lookup tables", table));
+
+    let mut out = vec![0; decoded_len_estimate(encoded.len())];
+    let split = measure(|| {
+        std::hint::black_box(decode(std::hint::black_box(&encoded)).unwrap());
+    });
+    let table = measure(|| {
+        decode_to_slice(
+            &tables,
+            std::hint::black_box(encoded.as_bytes()),
+            &mut out,
+            Padding::Pad,
+        )
+        .unwrap();
+    });
+    report("decode", ("split/stitch", split), ("lookup tables", table));
+
+    let parallel = Parallel::new();
+    let threaded = measure(|| {
+        std::hint::black_box(encode_parallel(
+            &Classic,
+            std::hint::black_box(&data),
+            Padding::Pad,
+            &parallel,
+        ));
+    });
+    let mut out = vec![0; encoded_len(data.len(), Padding::Pad).unwrap()];
+    let table = measure(|| {
+        encode_to_slice(&tables, std::hint::black_box(&data), &mut out, Padding::Pad).unwrap();
+    });
+    report("encode", ("lookup tables", table), ("parallel", threaded));
+
+    let threaded = measure(|| {
+        std::hint::black_box(
+            decode_parallel(
+                &Classic,
+                std::hint::black_box(&encoded),
+                Padding::Pad,
+                &parallel,
+            )
+            .unwrap(),
+        );
+    });
+    let mut out = vec![0; decoded_len_estimate(encoded.len())];
+    let table = measure(|| {
+        decode_to_slice(
+            &tables,
+            std::hint::black_box(encoded.as_bytes()),
+            &mut out,
+            Padding::Pad,
+        )
+        .unwrap();
+    });
+    report("decode", ("lookup tables", table), ("parallel", threaded));
+}
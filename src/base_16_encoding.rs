@@ -0,0 +1,288 @@
+// Base16, better known as hex: every byte is split into its two 4-bit halves (nibbles),
+// and each nibble is written as one of the 16 digits 0-9 and a-f.
+
+//eg
+// Input Data          A
+// Input Bits   01000001
+// Nibbles      0100 0001
+// Mapping         4    1
+
+// There is no padding, every byte takes exactly two digits.
+// Decoding accepts both cases, and the errors are the ones of base_64_encoding.
+use crate::base_64_encoding::DecodeError;
+use std::fmt;
+
+const LOWER_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const UPPER_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Lower,
+    Upper,
+}
+
+pub fn encode(data: &[u8]) -> String {
+    encode_with_separator(data, Case::Lower, None)
+}
+
+pub fn encode_upper(data: &[u8]) -> String {
+    encode_with_separator(data, Case::Upper, None)
+}
+
+// The separator goes between the bytes, like the ':' of a MAC address or a certificate fingerprint
+pub fn encode_with_separator(data: &[u8], case: Case, separator: Option<char>) -> String {
+    let digits = match case {
+        Case::Lower => LOWER_DIGITS,
+        Case::Upper => UPPER_DIGITS,
+    };
+    let mut out = String::with_capacity(data.len() * 3);
+    for (i, byte) in data.iter().enumerate() {
+        if let (Some(separator), true) = (separator, i > 0) {
+            out.push(separator);
+        }
+        //the high nibble is shifted down, the low one masked out
+        out.push(digits[(byte >> 4) as usize] as char);
+        out.push(digits[(byte & 0b00001111) as usize] as char);
+    }
+    out
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_with_separator(data, None)
+}
+
+// With a separator it has to be between every two bytes, and nowhere else
+pub fn decode_with_separator(data: &str, separator: Option<char>) -> Result<Vec<u8>, DecodeError> {
+    let bytes = data.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len() / 2);
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        if let (Some(separator), true) = (separator, offset > 0) {
+            // the separator is checked as a char, it might not be ascii
+            if !data[offset..].starts_with(separator) {
+                return Err(DecodeError::InvalidByte {
+                    offset,
+                    byte: bytes[offset],
+                });
+            }
+            offset += separator.len_utf8();
+        }
+        if offset + 2 > bytes.len() {
+            return Err(DecodeError::InvalidLength);
+        }
+        let high = nibble(bytes, offset)?;
+        let low = nibble(bytes, offset + 1)?;
+        out.push(high << 4 | low);
+        offset += 2;
+    }
+    Ok(out)
+}
+
+fn nibble(bytes: &[u8], offset: usize) -> Result<u8, DecodeError> {
+    let byte = bytes[offset];
+    match byte {
+        b'0'..=b'9' => Ok(byte - b'0'),
+        b'a'..=b'f' => Ok(byte - b'a' + 10),
+        b'A'..=b'F' => Ok(byte - b'A' + 10),
+        _ => Err(DecodeError::InvalidByte { offset, byte }),
+    }
+}
+
+// Percent-encoding, quoted-printable and the Q encoding of RFC 2047 all write a byte as a marker
+// followed by its two uppercase hex digits, like "%2F" or "=2F"
+pub(crate) fn escape(marker: u8, byte: u8) -> [u8; 3] {
+    [
+        marker,
+        UPPER_DIGITS[(byte >> 4) as usize],
+        UPPER_DIGITS[(byte & 0b00001111) as usize],
+    ]
+}
+
+pub(crate) fn push_escape(out: &mut String, marker: u8, byte: u8) {
+    out.extend(escape(marker, byte).iter().map(|&digit| digit as char));
+}
+
+// the byte written by the two hex digits at offset, in either case, None if they aren't both there
+pub(crate) fn hex_pair(bytes: &[u8], offset: usize) -> Option<u8> {
+    let digit = |offset: usize| (*bytes.get(offset)? as char).to_digit(16);
+    Some((digit(offset)? << 4 | digit(offset + 1)?) as u8)
+}
+
+// Formats data the way `hexdump -C` does: the offset, 16 bytes in two groups of 8,
+// and the same bytes as ascii with a '.' for anything that isn't printable.
+// A line that repeats the one before it is shown as a single '*', and the last line is the length.
+//
+// 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|
+// 00000010  02                                                |.|
+// 00000011
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let data = self.0;
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let mut previous: Option<&[u8]> = None;
+        let mut squeezing = false;
+        for (line, chunk) in data.chunks(16).enumerate() {
+            if previous == Some(chunk) {
+                if !squeezing {
+                    writeln!(f, "*")?;
+                    squeezing = true;
+                }
+                continue;
+            }
+            previous = Some(chunk);
+            squeezing = false;
+
+            write!(f, "{:08x}  ", line * 16)?;
+            for i in 0..16 {
+                match chunk.get(i) {
+                    Some(byte) => write!(f, "{:02x} ", byte)?,
+                    None => write!(f, "   ")?,
+                }
+                if i == 7 {
+                    write!(f, " ")?;
+                }
+            }
+            let ascii = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte == b' ' || byte.is_ascii_graphic() {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            writeln!(f, " |{}|", ascii)?;
+        }
+        writeln!(f, "{:08x}", data.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(
+            encode(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01]),
+            "deadbeef0001"
+        );
+        assert_eq!(encode_upper(&[0xde, 0xad, 0xbe, 0xef]), "DEADBEEF");
+        assert_eq!(
+            encode_with_separator(
+                &[0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6],
+                Case::Upper,
+                Some(':')
+            ),
+            "00:1B:63:84:45:E6"
+        );
+        assert_eq!(
+            encode_with_separator(&[0xca, 0xfe], Case::Lower, Some(' ')),
+            "ca fe"
+        );
+        assert_eq!(encode_with_separator(&[0xca], Case::Lower, Some(' ')), "ca");
+    }
+
+    #[test]
+    fn decoding() {
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        assert_eq!(decode(&encode(&data)).unwrap(), data);
+        assert_eq!(decode(&encode_upper(&data)).unwrap(), data);
+        assert_eq!(decode("DeadBeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(
+            decode_with_separator("00:1b:63", Some(':')).unwrap(),
+            [0x00, 0x1b, 0x63]
+        );
+        assert_eq!(
+            decode_with_separator("ca fe", Some(' ')).unwrap(),
+            [0xca, 0xfe]
+        );
+        assert_eq!(decode_with_separator("", Some(' ')).unwrap(), []);
+    }
+
+    #[test]
+    fn decoding_errors() {
+        assert_eq!(decode("abc"), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            decode("abcg"),
+            Err(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'g'
+            })
+        );
+        assert_eq!(
+            decode("ab cd"),
+            Err(DecodeError::InvalidByte {
+                offset: 2,
+                byte: b' '
+            })
+        );
+        assert_eq!(
+            decode("\u{e9}0"),
+            Err(DecodeError::InvalidByte {
+                offset: 0,
+                byte: 0xc3
+            })
+        );
+        assert_eq!(
+            decode_with_separator("00:1b-63", Some(':')),
+            Err(DecodeError::InvalidByte {
+                offset: 5,
+                byte: b'-'
+            })
+        );
+        assert_eq!(
+            decode_with_separator("00:1b:", Some(':')),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(
+            decode_with_separator(":00", Some(':')),
+            Err(DecodeError::InvalidByte {
+                offset: 0,
+                byte: b':'
+            })
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(&escape(b'=', 0x2f), b"=2F");
+        let mut out = String::new();
+        push_escape(&mut out, b'%', 0xc3);
+        assert_eq!(out, "%C3");
+
+        assert_eq!(hex_pair(b"%c3%C3", 1), Some(0xc3));
+        assert_eq!(hex_pair(b"%c3%C3", 4), Some(0xc3));
+        assert_eq!(hex_pair(b"%c", 1), None);
+        assert_eq!(hex_pair(b"%cg", 1), None);
+    }
+
+    #[test]
+    fn hexdump() {
+        assert_eq!(HexDump(b"").to_string(), "");
+        assert_eq!(
+            HexDump(b"Hello, world!\n\x00\x01\x02").to_string(),
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|\n\
+             00000010  02                                                |.|\n\
+             00000011\n"
+        );
+        // the repeated lines are squeezed into a '*'
+        let mut data = vec![0; 64];
+        data.push(0x7f);
+        assert_eq!(
+            HexDump(&data).to_string(),
+            "00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|\n\
+             *\n\
+             00000040  7f                                                |.|\n\
+             00000041\n"
+        );
+    }
+}
@@ -0,0 +1,465 @@
+// Base32 works like Base64, but with 5 bits per character instead of 6.
+// The lowest common multiple of 8 and 5 is 40, so the input is divided into blocks of 5 bytes
+// which are split into 8 groups of 5 bits, each mapped to 1 of 32 characters.
+
+//eg
+// Input Data          f        o
+// Input Bits   01100110 01101111
+// Bit Groups   01100 11001 10111 1(0000)
+// Mapping          M     Z     X     Q
+// Padding      M Z X Q = = = =
+
+// A last block of 1, 2, 3 or 4 bytes needs 2, 4, 5 or 7 characters, the rest of the 8 is padding.
+// RFC 4648 defines the standard alphabet (A-Z, 2-7, used for TOTP secrets), and the "extended hex"
+// alphabet (0-9, A-V) that keeps the sort order of the data, which is why DNSSEC NSEC3 uses it.
+// Crockford's alphabet is made for humans: no padding, no I, L, O or U, decoding is case-insensitive,
+// reads I and L as 1 and O as 0, ignores hyphens, and an optional check symbol can catch typos.
+//
+// The padding policy and the decoding errors are the ones of base_64_encoding.
+use crate::base_64_encoding::{DecodeError, Padding};
+use std::iter::FromIterator;
+
+// The same operations as base_64_encoding::Alphabet, on 5-bit indices, padding included:
+// the codec only asks get_optional_padding_char, and an alphabet without padding returns None there,
+// which makes its get_padding_char meaningless.
+// is_ignored tells which characters the decoder skips, on top of those it has whitespace.
+pub trait Base32Alphabet {
+    fn get_char_for_index(&self, index: u8) -> Option<char>;
+    fn get_index_for_char(&self, character: char) -> Option<u8>;
+    fn get_padding_char(&self) -> char;
+    fn get_optional_padding_char(&self) -> Option<char> {
+        Some(self.get_padding_char())
+    }
+    fn is_ignored(&self, character: char) -> bool {
+        character.is_ascii_whitespace()
+    }
+}
+
+pub struct Standard;
+
+impl Base32Alphabet for Standard {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        match index {
+            0..=25 => Some((b'A' + index) as char),
+            26..=31 => Some((b'2' + index - 26) as char),
+            _ => None,
+        }
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        match character {
+            'A'..='Z' => Some(character as u8 - b'A'),
+            '2'..='7' => Some(character as u8 - b'2' + 26),
+            _ => None,
+        }
+    }
+
+    fn get_padding_char(&self) -> char {
+        '='
+    }
+}
+
+pub struct Hex;
+
+impl Base32Alphabet for Hex {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        match index {
+            0..=9 => Some((b'0' + index) as char),
+            10..=31 => Some((b'A' + index - 10) as char),
+            _ => None,
+        }
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        match character {
+            '0'..='9' => Some(character as u8 - b'0'),
+            'A'..='V' => Some(character as u8 - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    fn get_padding_char(&self) -> char {
+        '='
+    }
+}
+
+const CROCKFORD_SYMBOLS: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// the check symbol is a number modulo 37, so it needs 5 more symbols
+const CROCKFORD_CHECK_SYMBOLS: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+pub struct Crockford;
+
+impl Base32Alphabet for Crockford {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        CROCKFORD_SYMBOLS
+            .get(index as usize)
+            .map(|&symbol| symbol as char)
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        // the letters that are easily mistaken for digits are read as those digits
+        let character = match character.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            character => character,
+        };
+        CROCKFORD_SYMBOLS
+            .iter()
+            .position(|&symbol| symbol as char == character)
+            .map(|index| index as u8)
+    }
+
+    // never used, see get_optional_padding_char
+    fn get_padding_char(&self) -> char {
+        '='
+    }
+
+    fn get_optional_padding_char(&self) -> Option<char> {
+        None
+    }
+
+    fn is_ignored(&self, character: char) -> bool {
+        character == '-' || character.is_ascii_whitespace()
+    }
+}
+
+// Decodes lowercase input with an uppercase alphabet, like the unpadded lowercase secrets of TOTP apps.
+// Encoding is left to the wrapped alphabet.
+pub struct CaseInsensitive<T: Base32Alphabet>(pub T);
+
+impl<T: Base32Alphabet> Base32Alphabet for CaseInsensitive<T> {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        self.0.get_char_for_index(index)
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        self.0
+            .get_index_for_char(character.to_ascii_uppercase())
+            .or_else(|| self.0.get_index_for_char(character.to_ascii_lowercase()))
+    }
+
+    fn get_padding_char(&self) -> char {
+        self.0.get_padding_char()
+    }
+
+    fn get_optional_padding_char(&self) -> Option<char> {
+        self.0.get_optional_padding_char()
+    }
+
+    fn is_ignored(&self, character: char) -> bool {
+        self.0.is_ignored(character)
+    }
+}
+
+// the number of characters a block of 0 to 5 bytes takes, without padding
+const CHARS_FOR_BYTES: [usize; 6] = [0, 2, 4, 5, 7, 8];
+
+// Divid the input into a block of up-to 5 bytes (40 bits) and split it into up-to 8 groups of 5 bits.
+// The bits are gathered in a single integer, the last group is filled up with zeroes.
+fn split(chunk: &[u8]) -> Vec<u8> {
+    let bits = chunk.iter().enumerate().fold(0_u64, |bits, (i, &byte)| {
+        bits | (byte as u64) << (32 - 8 * i)
+    });
+    (0..CHARS_FOR_BYTES[chunk.len()])
+        .map(|i| (bits >> (35 - 5 * i) & 0b11111) as u8)
+        .collect()
+}
+
+// The reverse of split: up-to 8 indices back into the up-to 5 whole bytes they carry.
+// The bits left over in the last index are the zeroes split filled it with.
+fn stitch(indices: &[u8]) -> Vec<u8> {
+    let bits = indices.iter().enumerate().fold(0_u64, |bits, (i, &index)| {
+        bits | (index as u64) << (35 - 5 * i)
+    });
+    (0..indices.len() * 5 / 8)
+        .map(|i| (bits >> (32 - 8 * i)) as u8)
+        .collect()
+}
+
+pub fn encode_using_alphabet<T: Base32Alphabet>(
+    alphabet: &T,
+    data: &[u8],
+    padding: Padding,
+) -> String {
+    let padding_char = match padding {
+        Padding::NoPad => None,
+        _ => alphabet.get_optional_padding_char(),
+    };
+
+    let encoded = data.chunks(5).flat_map(|chunk| {
+        let mut out = split(chunk)
+            .into_iter()
+            .filter_map(|index| alphabet.get_char_for_index(index))
+            .collect::<Vec<char>>();
+        if let Some(padding_char) = padding_char {
+            out.resize(8, padding_char);
+        }
+        out
+    });
+
+    String::from_iter(encoded)
+}
+
+pub fn decode_using_alphabet<T: Base32Alphabet>(
+    alphabet: &T,
+    data: &str,
+    padding: Padding,
+) -> Result<Vec<u8>, DecodeError> {
+    // every char keeps its byte offset so errors can point back into the input
+    let chars = data
+        .char_indices()
+        .filter(|(_, character)| !alphabet.is_ignored(*character))
+        .collect::<Vec<(usize, char)>>();
+
+    // the padding can only be at the end of the input, so we count it from there
+    let padding_char = alphabet.get_optional_padding_char();
+    let unpadded_len = chars
+        .iter()
+        .rposition(|(_, character)| Some(*character) != padding_char)
+        .map_or(0, |position| position + 1);
+    let pad_len = chars.len() - unpadded_len;
+    check_padding(padding, padding_char.is_some(), unpadded_len, pad_len)?;
+
+    let mut result = Vec::with_capacity(unpadded_len * 5 / 8);
+    for chunk in chars[..unpadded_len].chunks(8) {
+        let indices = chunk
+            .iter()
+            .map(|&(offset, character)| {
+                alphabet
+                    .get_index_for_char(character)
+                    .ok_or_else(|| DecodeError::InvalidByte {
+                        offset,
+                        byte: data.as_bytes()[offset],
+                    })
+            })
+            .collect::<Result<Vec<u8>, DecodeError>>()?;
+        result.extend(stitch(&indices));
+    }
+    Ok(result)
+}
+
+// the Base32 version of Padding::check, a last block can only have 2, 4, 5 or 7 characters
+fn check_padding(
+    padding: Padding,
+    padded: bool,
+    unpadded_len: usize,
+    pad_len: usize,
+) -> Result<(), DecodeError> {
+    let remainder = unpadded_len % 8;
+    if !CHARS_FOR_BYTES.contains(&remainder) {
+        return Err(DecodeError::InvalidLength);
+    }
+    let required = (8 - remainder) % 8;
+    let valid = match padding {
+        // an alphabet without padding character is never padded
+        _ if !padded => true,
+        Padding::Pad => pad_len == required,
+        Padding::NoPad => pad_len == 0,
+        Padding::Indifferent => pad_len == 0 || pad_len == required,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(DecodeError::InvalidPadding)
+    }
+}
+
+pub fn encode(data: &[u8]) -> String {
+    encode_using_alphabet(&Standard, data, Padding::Pad)
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_using_alphabet(&Standard, data, Padding::Pad)
+}
+
+pub fn encode_hex(data: &[u8]) -> String {
+    encode_using_alphabet(&Hex, data, Padding::Pad)
+}
+
+pub fn decode_hex(data: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_using_alphabet(&Hex, data, Padding::Pad)
+}
+
+pub fn encode_crockford(data: &[u8]) -> String {
+    encode_using_alphabet(&Crockford, data, Padding::NoPad)
+}
+
+pub fn decode_crockford(data: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_using_alphabet(&Crockford, data, Padding::NoPad)
+}
+
+// The encoded string is the number the check symbol is computed from, the symbols being its base 32 digits.
+// That number modulo 37 is appended, from an alphabet of 37 symbols.
+pub fn encode_crockford_with_check(data: &[u8]) -> String {
+    let mut encoded = encode_crockford(data);
+    let check = crockford_check(
+        encoded
+            .chars()
+            .filter_map(|character| Crockford.get_index_for_char(character)),
+    );
+    encoded.push(CROCKFORD_CHECK_SYMBOLS[check] as char);
+    encoded
+}
+
+// A check symbol that doesn't match the data is reported as an InvalidLastSymbol
+pub fn decode_crockford_with_check(data: &str) -> Result<Vec<u8>, DecodeError> {
+    let (offset, _) = data
+        .char_indices()
+        .rev()
+        .find(|(_, character)| !Crockford.is_ignored(*character))
+        .ok_or(DecodeError::InvalidLength)?;
+    let byte = data.as_bytes()[offset];
+    let expected = CROCKFORD_CHECK_SYMBOLS
+        .iter()
+        .position(|&symbol| symbol == byte.to_ascii_uppercase())
+        .ok_or(DecodeError::InvalidByte { offset, byte })?;
+
+    let encoded = &data[..offset];
+    let decoded = decode_crockford(encoded)?;
+    let check = crockford_check(
+        encoded
+            .chars()
+            .filter_map(|character| Crockford.get_index_for_char(character)),
+    );
+    if check != expected {
+        return Err(DecodeError::InvalidLastSymbol { offset, byte });
+    }
+    Ok(decoded)
+}
+
+// the value of the base 32 digits modulo 37, computed digit by digit so it never overflows
+fn crockford_check<I: Iterator<Item = u8>>(indices: I) -> usize {
+    indices.fold(0, |check, index| (check * 32 + index as usize) % 37)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_4648_vectors() {
+        let vectors = [
+            ("", "", ""),
+            ("f", "MY======", "CO======"),
+            ("fo", "MZXQ====", "CPNG===="),
+            ("foo", "MZXW6===", "CPNMU==="),
+            ("foob", "MZXW6YQ=", "CPNMUOG="),
+            ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+            ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+        ];
+        for (plain, base32, base32_hex) in vectors.iter() {
+            assert_eq!(encode(plain.as_bytes()), *base32);
+            assert_eq!(decode(base32).unwrap(), plain.as_bytes());
+            assert_eq!(encode_hex(plain.as_bytes()), *base32_hex);
+            assert_eq!(decode_hex(base32_hex).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = (0..=255_u8).rev().collect::<Vec<u8>>();
+        for len in 0..=40 {
+            for padding in [Padding::Pad, Padding::NoPad].iter() {
+                let encoded = encode_using_alphabet(&Standard, &data[..len], *padding);
+                assert_eq!(
+                    decode_using_alphabet(&Standard, &encoded, *padding).unwrap(),
+                    &data[..len]
+                );
+            }
+            assert_eq!(
+                decode_crockford(&encode_crockford(&data[..len])).unwrap(),
+                &data[..len]
+            );
+        }
+    }
+
+    #[test]
+    fn padding_rules() {
+        assert_eq!(
+            encode_using_alphabet(&Standard, b"foob", Padding::NoPad),
+            "MZXW6YQ"
+        );
+        assert_eq!(
+            decode_using_alphabet(&Standard, "MZXW6YQ", Padding::NoPad).unwrap(),
+            b"foob"
+        );
+        assert_eq!(
+            decode_using_alphabet(&Standard, "MZXW6YQ", Padding::Indifferent).unwrap(),
+            b"foob"
+        );
+        assert_eq!(decode("MZXW6YQ"), Err(DecodeError::InvalidPadding));
+        assert_eq!(decode("MZXW6Y=="), Err(DecodeError::InvalidLength));
+        assert_eq!(decode("MZX====="), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            decode("MY======MY======"),
+            Err(DecodeError::InvalidByte {
+                offset: 2,
+                byte: b'='
+            })
+        );
+    }
+
+    #[test]
+    fn case_insensitive_decoding() {
+        // a TOTP secret the way authenticator apps show it
+        let secret = "jbsw y3dp ehpk 3pxp";
+        assert_eq!(
+            decode(secret),
+            Err(DecodeError::InvalidByte {
+                offset: 0,
+                byte: b'j'
+            })
+        );
+        let decoded =
+            decode_using_alphabet(&CaseInsensitive(Standard), secret, Padding::Indifferent)
+                .unwrap();
+        assert_eq!(decoded, b"Hello!\xde\xad\xbe\xef");
+        assert_eq!(
+            decode_using_alphabet(&CaseInsensitive(Hex), "cpnmuoj1", Padding::Pad).unwrap(),
+            b"fooba"
+        );
+    }
+
+    #[test]
+    fn crockford() {
+        assert_eq!(encode_crockford(b"foobar"), "CSQPYRK1E8");
+        assert_eq!(decode_crockford("csqp-yrk1-e8").unwrap(), b"foobar");
+        // O is read as 0, I and L as 1
+        assert_eq!(decode_crockford("ZZZO").unwrap(), [0xff, 0xfe]);
+        assert_eq!(decode_crockford("CSQPYRKIE8").unwrap(), b"foobar");
+        assert_eq!(decode_crockford("CSQPYRKLE8").unwrap(), b"foobar");
+        assert_eq!(
+            decode_crockford("CSQPYRKUE8"),
+            Err(DecodeError::InvalidByte {
+                offset: 7,
+                byte: b'U'
+            })
+        );
+
+        assert_eq!(encode_crockford_with_check(b"foobar"), "CSQPYRK1E8R");
+        assert_eq!(encode_crockford_with_check(b"Hello"), "91JPRV3FG");
+        assert_eq!(encode_crockford_with_check(&[0xff, 0xfe]), "ZZZ01");
+        assert_eq!(
+            decode_crockford_with_check("csqpyrk1e8r").unwrap(),
+            b"foobar"
+        );
+        assert_eq!(
+            decode_crockford_with_check("CSQPYRK1E9R"),
+            Err(DecodeError::InvalidLastSymbol {
+                offset: 10,
+                byte: b'R'
+            })
+        );
+        assert_eq!(
+            decode_crockford_with_check("CSQPYRK1E8!"),
+            Err(DecodeError::InvalidByte {
+                offset: 10,
+                byte: b'!'
+            })
+        );
+        assert_eq!(
+            decode_crockford_with_check(""),
+            Err(DecodeError::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+// Base45 (RFC 9285) is made for QR codes: their alphanumeric mode stores 45 characters
+// (0-9, A-Z, space and $%*+-./:) in 5.5 bits each, which beats Base64 squeezed through byte mode.
+// Every 2 bytes are read as a big-endian number n = a * 256 + b, which is below 45^3 and so fits
+// in 3 base 45 digits c + d * 45 + e * 45^2. They are written least significant first: c, d, e.
+// A last single byte only needs 2 digits.
+
+//eg
+// Input Data           A         B
+// Number       65 * 256 + 66 = 16706
+// Digits       16706 = 11 + 11 * 45 + 8 * 45^2
+// Mapping          B  B  8
+
+// Not every 3 digits are a valid pair of bytes: 45^3 - 1 is 91124, anything above 65535 is rejected,
+// and so is a last pair of digits above 255.
+use crate::base_64_encoding::DecodeError;
+use std::fmt;
+
+const ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base45Error {
+    // a byte outside of the alphabet, offset is the byte position in the input
+    Decode(DecodeError),
+    // a single character is left over at the end
+    InvalidLength,
+    // the digits starting at offset are worth more than the bytes they stand for can hold
+    Overflow { offset: usize },
+}
+
+impl fmt::Display for Base45Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Base45Error::Decode(err) => err.fmt(f),
+            Base45Error::InvalidLength => write!(f, "invalid input length"),
+            Base45Error::Overflow { offset } => write!(f, "value at offset {} overflows", offset),
+        }
+    }
+}
+
+impl std::error::Error for Base45Error {}
+
+impl From<DecodeError> for Base45Error {
+    fn from(err: DecodeError) -> Self {
+        Base45Error::Decode(err)
+    }
+}
+
+pub fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(2) * 3);
+    for chunk in data.chunks(2) {
+        let (mut value, digits) = match *chunk {
+            [a, b] => ((a as usize) << 8 | b as usize, 3),
+            [a] => (a as usize, 2),
+            _ => unreachable!(),
+        };
+        for _ in 0..digits {
+            out.push(ALPHABET[value % 45] as char);
+            value /= 45;
+        }
+    }
+    out
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>, Base45Error> {
+    let bytes = data.as_bytes();
+    if bytes.len() % 3 == 1 {
+        return Err(Base45Error::InvalidLength);
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() / 3 * 2 + 1);
+    for (group, chunk) in bytes.chunks(3).enumerate() {
+        let offset = group * 3;
+        // the digits are least significant first, so they are folded from the back
+        let mut value = 0;
+        for (i, &byte) in chunk.iter().enumerate().rev() {
+            let digit = ALPHABET.iter().position(|&symbol| symbol == byte).ok_or(
+                DecodeError::InvalidByte {
+                    offset: offset + i,
+                    byte,
+                },
+            )?;
+            value = value * 45 + digit;
+        }
+
+        if chunk.len() == 3 {
+            if value > 0xffff {
+                return Err(Base45Error::Overflow { offset });
+            }
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        } else {
+            if value > 0xff {
+                return Err(Base45Error::Overflow { offset });
+            }
+            out.push(value as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_9285_vectors() {
+        let vectors = [
+            ("AB", "BB8"),
+            ("Hello!!", "%69 VD92EX0"),
+            ("base-45", "UJCLQE7W581"),
+            ("ietf!", "QED8WEX0"),
+            ("", ""),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(encode(plain.as_bytes()), *encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = (0..=255_u8).rev().collect::<Vec<u8>>();
+        for len in 0..=20 {
+            assert_eq!(decode(&encode(&data[..len])).unwrap(), &data[..len]);
+        }
+        assert_eq!(encode(&[0xff, 0xff]), "FGW");
+        assert_eq!(decode("FGW").unwrap(), [0xff, 0xff]);
+    }
+
+    #[test]
+    fn decoding_errors() {
+        // 65536, one above the highest pair of bytes
+        assert_eq!(decode("GGW"), Err(Base45Error::Overflow { offset: 0 }));
+        assert_eq!(decode("BB8:::"), Err(Base45Error::Overflow { offset: 3 }));
+        // 256, one above the highest single byte
+        assert_eq!(decode("BB8V5"), Err(Base45Error::Overflow { offset: 3 }));
+        assert_eq!(decode("BB8B"), Err(Base45Error::InvalidLength));
+        assert_eq!(
+            decode("BB8bB8"),
+            Err(Base45Error::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'b'
+            }))
+        );
+    }
+}
@@ -0,0 +1,227 @@
+// Base58 doesn't split the input into groups of bits: 58 is not a power of two, so no number of bits
+// maps onto a whole number of characters. Instead the whole input is read as one big-endian number,
+// which is converted to base 58 by repeated division, the same way a number is written in decimal.
+// The Bitcoin alphabet leaves out 0, O, I and l, which are easily mistaken for each other.
+//
+// A number has no leading zeroes, so each leading zero byte of the input is written as a '1'
+// (the character for 0) to keep it. There is no padding.
+//
+// Base58Check appends a checksum before encoding: the first 4 bytes of SHA-256(SHA-256(data)),
+// so a mistyped address is rejected instead of sending money into the void.
+use crate::base_64_encoding::DecodeError;
+use crate::sha_256::sha256;
+use std::fmt;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn get_index_for_byte(byte: u8) -> Option<u8> {
+    ALPHABET
+        .iter()
+        .position(|&symbol| symbol == byte)
+        .map(|index| index as u8)
+}
+
+pub fn encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|&&byte| byte == 0).count();
+
+    // the base 58 digits of the number, least significant first.
+    // Every byte multiplies the number so far by 256 and adds itself, carried through the digits
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in &data[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut out = String::with_capacity(zeros + digits.len());
+    out.extend(std::iter::repeat_n('1', zeros));
+    out.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| ALPHABET[digit as usize] as char),
+    );
+    out
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>, DecodeError> {
+    let input = data.as_bytes();
+    let ones = input.iter().take_while(|&&byte| byte == b'1').count();
+
+    // the reverse of encode: every character multiplies the number by 58, in base 256 this time
+    let mut bytes: Vec<u8> = Vec::with_capacity(input.len() * 733 / 1000 + 1);
+    for (offset, &byte) in input.iter().enumerate().skip(ones) {
+        let mut carry =
+            get_index_for_byte(byte).ok_or(DecodeError::InvalidByte { offset, byte })? as u32;
+        for value in bytes.iter_mut() {
+            carry += (*value as u32) * 58;
+            *value = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut out = vec![0; ones];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckError {
+    Decode(DecodeError),
+    // the checksum computed from the data, and the one found after it
+    InvalidChecksum { expected: [u8; 4], found: [u8; 4] },
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Decode(err) => err.fmt(f),
+            CheckError::InvalidChecksum { expected, found } => {
+                write!(
+                    f,
+                    "invalid checksum {:02x?}, expected {:02x?}",
+                    found, expected
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+impl From<DecodeError> for CheckError {
+    fn from(err: DecodeError) -> Self {
+        CheckError::Decode(err)
+    }
+}
+
+fn checksum(data: &[u8]) -> [u8; 4] {
+    let hash = sha256(&sha256(data));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+// data is everything the checksum covers, for a Bitcoin address that's the version byte and the hash
+pub fn encode_check(data: &[u8]) -> String {
+    let mut with_checksum = Vec::with_capacity(data.len() + 4);
+    with_checksum.extend_from_slice(data);
+    with_checksum.extend_from_slice(&checksum(data));
+    encode(&with_checksum)
+}
+
+// returns the data without its checksum
+pub fn decode_check(data: &str) -> Result<Vec<u8>, CheckError> {
+    let mut decoded = decode(data)?;
+    if decoded.len() < 4 {
+        return Err(DecodeError::InvalidLength.into());
+    }
+    let split = decoded.len() - 4;
+    let found = [
+        decoded[split],
+        decoded[split + 1],
+        decoded[split + 2],
+        decoded[split + 3],
+    ];
+    decoded.truncate(split);
+
+    let expected = checksum(&decoded);
+    if expected != found {
+        return Err(CheckError::InvalidChecksum { expected, found });
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_16_encoding;
+
+    // from Bitcoin Core's base58_encode_decode.json
+    const BITCOIN_VECTORS: [(&str, &str); 13] = [
+        ("", ""),
+        ("61", "2g"),
+        ("626262", "a3gV"),
+        ("636363", "aPEr"),
+        ("73696d706c792061206c6f6e6720737472696e67", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+        ("00eb15231dfceb60925886b67d065299925915aeb172c06647", "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L"),
+        ("516b6fcd0f", "ABnLTmg"),
+        ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+        ("572e4794", "3EFU7m"),
+        ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+        ("10c8511e", "Rt5zm"),
+        ("00000000000000000000", "1111111111"),
+        (
+            "000111d38e5fc9071ffcd20b4a763cc9ae4f252bb4e48fd66a835e252ada93ff480d6dd43dc62a641155a5",
+            "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz",
+        ),
+    ];
+
+    #[test]
+    fn bitcoin_vectors() {
+        for (hex, encoded) in BITCOIN_VECTORS.iter() {
+            let data = base_16_encoding::decode(hex).unwrap();
+            assert_eq!(encode(&data), *encoded);
+            assert_eq!(decode(encoded).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn invalid_characters() {
+        assert_eq!(
+            decode("3SEo3LWLoPnt0"),
+            Err(DecodeError::InvalidByte {
+                offset: 12,
+                byte: b'0'
+            })
+        );
+        assert_eq!(
+            decode("1l"),
+            Err(DecodeError::InvalidByte {
+                offset: 1,
+                byte: b'l'
+            })
+        );
+        assert_eq!(
+            decode(" 2g"),
+            Err(DecodeError::InvalidByte {
+                offset: 0,
+                byte: b' '
+            })
+        );
+    }
+
+    #[test]
+    fn bitcoin_address() {
+        // version 0 followed by the hash160 of a public key
+        let data = base_16_encoding::decode("00010966776006953d5567439e5e39f86a0d273bee").unwrap();
+        assert_eq!(encode_check(&data), "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM");
+        assert_eq!(
+            decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM").unwrap(),
+            data
+        );
+
+        // a single character off
+        assert_eq!(
+            decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvm"),
+            Err(CheckError::InvalidChecksum {
+                expected: [0xd6, 0x19, 0x67, 0xf6],
+                found: [0xd6, 0x19, 0x68, 0x0e],
+            })
+        );
+        assert_eq!(
+            decode_check("2g"),
+            Err(CheckError::Decode(DecodeError::InvalidLength))
+        );
+        assert_eq!(decode_check(&encode_check(b"")).unwrap(), b"");
+    }
+}
@@ -40,14 +40,57 @@
 // There are basically three operations that our alphabet should be able to perform;
 // Going from an index to a character, going from a character back to the original 6-bit index
 // and getting the character used for padding.
+// The codec only ever asks get_optional_padding_char, which is the padding character unless the alphabet
+// is never padded (crypt, bcrypt, the IMAP one), those override it to return None.
+// get_padding_char means nothing for such an alphabet: whatever it returns is never written or accepted,
+// and may even be one of its 64 symbols.
+use std::fmt;
 use std::iter::FromIterator;
 
+mod constant_time;
+mod custom_alphabet;
+mod decoder_reader;
+mod encoder_writer;
+mod engine;
+mod lookup_tables;
+mod parallel;
+pub use constant_time::{decode_constant_time, encode_constant_time, ConstantTimeAlphabet};
+pub use custom_alphabet::{AlphabetError, CustomAlphabet, BCRYPT, CRYPT, IMAP_MAILBOX};
+pub use decoder_reader::DecoderReader;
+pub use encoder_writer::EncoderWriter;
+pub use engine::{Config, Engine, MIME, STANDARD, URL_SAFE};
+pub use lookup_tables::{
+    decode_to_slice, decoded_len_estimate, encode_to_slice, encoded_len, DecodeSliceError, LookupTables,
+    OutputTooSmall,
+};
+pub use parallel::{decode_parallel, encode_parallel, Parallel};
+
 pub trait Alphabet {
     fn get_char_for_index(&self, index: u8) -> Option<char>;
     fn get_index_for_char(&self, character: char) -> Option<u8>;
     fn get_padding_char(&self) -> char;
+    fn get_optional_padding_char(&self) -> Option<char> {
+        Some(self.get_padding_char())
+    }
+}
+
+// an alphabet can be borrowed wherever one is taken by value
+impl<T: Alphabet> Alphabet for &T {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        (**self).get_char_for_index(index)
+    }
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        (**self).get_index_for_char(character)
+    }
+    fn get_padding_char(&self) -> char {
+        (**self).get_padding_char()
+    }
+    fn get_optional_padding_char(&self) -> Option<char> {
+        (**self).get_optional_padding_char()
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Classic;
 
 const UPPERCASEOFFSET: i8 = 65;
@@ -70,6 +113,10 @@ impl Alphabet for Classic {
     }
 
     fn get_index_for_char(&self, character: char) -> Option<u8> {
+        // anything outside of ascii would be truncated by the cast below and could alias a valid character
+        if !character.is_ascii() {
+            return None;
+        }
         let character = character as i8;
 
         let base64_index = match character {
@@ -88,10 +135,212 @@ impl Alphabet for Classic {
     }
 }
 
+// The URL and filename safe alphabet (RFC 4648 section 5) is identical to the classic one,
+// except that index 62 and 63 map to '-' and '_' instead of '+' and '/'.
+// Both of those have a special meaning in URLs and paths, the replacements don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrlSafe;
+
+impl Alphabet for UrlSafe {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        match index {
+            62 => Some('-'),
+            63 => Some('_'),
+            _ => Classic.get_char_for_index(index),
+        }
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        match character {
+            '-' => Some(62),
+            '_' => Some(63),
+            '+' | '/' => None,
+            _ => Classic.get_index_for_char(character),
+        }
+    }
+    fn get_padding_char(&self) -> char {
+        '='
+    }
+}
+
+// Padding is not always wanted: JWTs and WebAuthn for example strip it, since the decoder can work out
+// how many bytes the last quantum holds from the number of characters left over (2 chars -> 1 byte,
+// 3 chars -> 2 bytes). The policy controls both directions:
+// Pad always writes padding and requires it when decoding,
+// NoPad never writes padding and rejects it when decoding,
+// Indifferent writes padding but accepts input with or without it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    Pad,
+    NoPad,
+    Indifferent,
+}
+
+impl Padding {
+    // an alphabet without a padding character can't be padded, whatever the policy says
+    fn for_alphabet<T: Alphabet>(self, alphabet: &T) -> Padding {
+        match alphabet.get_optional_padding_char() {
+            Some(_) => self,
+            None => Padding::NoPad,
+        }
+    }
+
+    // unpadded_len is the number of characters before the trailing padding, pad_len the number of padding characters
+    fn check(&self, unpadded_len: usize, pad_len: usize) -> Result<(), DecodeError> {
+        let remainder = unpadded_len % 4;
+        // a single character can only carry 6 bits, which is not enough for a byte
+        if remainder == 1 {
+            return Err(DecodeError::InvalidLength);
+        }
+        let required = (4 - remainder) % 4;
+        let valid = match self {
+            Padding::Pad => pad_len == required,
+            Padding::NoPad => pad_len == 0,
+            Padding::Indifferent => pad_len == 0 || pad_len == required,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(DecodeError::InvalidPadding)
+        }
+    }
+}
+
+// How picky the decoder is about input that doesn't come straight out of our encoder.
+// Lenient is what decode has always done: padding characters in the middle of the input are skipped,
+// and the bits of the last symbol that don't make a whole byte are ignored, so QR== decodes like QQ==.
+// Strict only accepts the canonical encoding: no whitespace, padding only at the end and those left over bits
+// all zero. Two different strings can then never decode to the same bytes, which matters when they are signed.
+// Wrapped input has to have its line breaks taken out before it is decoded strictly.
+// Forgiving follows the WHATWG "forgiving-base64" decode used by browsers (atob):
+// padding is optional but must be complete if present, it can only be at the end, left over bits are ignored.
+// Lenient and Forgiving skip whitespace. In every mode only the alphabet's own padding character counts
+// as padding, any other character is an InvalidByte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strictness {
+    Lenient,
+    Strict,
+    Forgiving,
+}
+
+// Everything that can go wrong while decoding. Offsets are byte positions in the input,
+// and byte is the (first) byte found there, so the caller can point at the exact spot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    // a character that is not part of the alphabet
+    InvalidByte { offset: usize, byte: u8 },
+    // the characters can't be grouped into quanta, e.g. a single character is left over at the end
+    InvalidLength,
+    // padding is missing, in excess, or not allowed by the padding policy
+    InvalidPadding,
+    // the last symbol has bits set that don't belong to any byte
+    InvalidLastSymbol { offset: usize, byte: u8 },
+}
+
+impl DecodeError {
+    // the same error with its offset moved by `by`, for Base64 decoded out of a larger text
+    pub fn shifted(self, by: usize) -> DecodeError {
+        match self {
+            DecodeError::InvalidByte { offset, byte } => DecodeError::InvalidByte {
+                offset: offset + by,
+                byte,
+            },
+            DecodeError::InvalidLastSymbol { offset, byte } => DecodeError::InvalidLastSymbol {
+                offset: offset + by,
+                byte,
+            },
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidByte { offset, byte } => {
+                write!(f, "invalid byte {:#04x} at offset {}", byte, offset)
+            }
+            DecodeError::InvalidLength => write!(f, "invalid input length"),
+            DecodeError::InvalidPadding => write!(f, "invalid padding"),
+            DecodeError::InvalidLastSymbol { offset, byte } => {
+                write!(f, "invalid last symbol {:#04x} at offset {}", byte, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// the byte reported for a character, for anything outside of ascii that's the first byte of its utf-8 encoding
+fn first_byte(character: char) -> u8 {
+    let mut buffer = [0; 4];
+    character.encode_utf8(&mut buffer);
+    buffer[0]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+// The lines of a text read line by line (armor, uuencode): the line number counted from 1,
+// the offset of the line in the text, and the line without its ending, LF or CRLF
+pub(crate) fn numbered_lines(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    text.split('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len() + 1;
+            Some((start, line))
+        })
+        .enumerate()
+        .map(|(index, (offset, line))| (index + 1, offset, line.strip_suffix('\r').unwrap_or(line)))
+}
+
+// Mail (RFC 2045) limits encoded lines to 76 characters, PEM (RFC 7468) to 64.
+// A line ending is inserted every width characters, there is none after the last line.
+// A width of 0 disables wrapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineWrap {
+    pub width: usize,
+    pub ending: LineEnding,
+}
+
+impl LineWrap {
+    pub const MIME: LineWrap = LineWrap { width: 76, ending: LineEnding::CrLf };
+    pub const PEM: LineWrap = LineWrap { width: 64, ending: LineEnding::Lf };
+
+    // appends encoded to out, breaking the line whenever column reaches the width.
+    // column carries over between calls so a stream can be wrapped piece by piece.
+    fn wrap_into(&self, encoded: &str, column: &mut usize, out: &mut String) {
+        if self.width == 0 {
+            out.push_str(encoded);
+            return;
+        }
+        for character in encoded.chars() {
+            if *column == self.width {
+                out.push_str(self.ending.as_str());
+                *column = 0;
+            }
+            out.push(character);
+            *column += 1;
+        }
+    }
+}
+
 // Divid the input bytes stream into blocks of 3 bytes (24 bits)
 // It converts the input of up-to 3 bytes into an output of up-to 4 bytes.
 // Essentially converting the 8-bit unsigned integers into 6-bit.
-fn split(chunk: &[u8]) -> Vec<u8> {
+pub(crate) fn split(chunk: &[u8]) -> Vec<u8> {
 
     match chunk.len() {
         1 => vec![&chunk[0] >> 2, (&chunk[0] & 0b00000011) << 4],
@@ -112,23 +361,41 @@ fn split(chunk: &[u8]) -> Vec<u8> {
 
 // slice the input data into 3-byte chunks and run them through our split function. Once they're split,
 //  we can convert each chunk by looking up the 6-bit number in our alphabet
-fn encode_using_alphabet<T: Alphabet>(alphabet: &T, data: &[u8]) -> String {
+pub fn encode_using_alphabet<T: Alphabet>(alphabet: &T, data: &[u8]) -> String {
+    encode_with_padding(alphabet, data, Padding::Pad)
+}
+
+pub fn encode_with_padding<T: Alphabet>(alphabet: &T, data: &[u8], padding: Padding) -> String {
     let encoded = data
         .chunks(3)
         .map(split)
-        .flat_map(|chunk| encode_chunk(alphabet, chunk));
+        .flat_map(|chunk| encode_chunk(alphabet, chunk, padding));
 
     String::from_iter(encoded)
 }
 
-fn encode_chunk<T: Alphabet>(alphabet: &T, chunk: Vec<u8>) -> Vec<char> {
-    //pre filling the buffer with 4 padding characters
-    let mut out = vec![alphabet.get_padding_char(); 4];
-    //iterate over the chunk
-    for i in 0..chunk.len() {
-        //map index to char an d replace if exist
-        if let Some(chr) = alphabet.get_char_for_index(chunk[i]) {
-            out[i] = chr;
+pub fn encode_wrapped<T: Alphabet>(
+    alphabet: &T,
+    data: &[u8],
+    padding: Padding,
+    wrap: LineWrap,
+) -> String {
+    let encoded = encode_with_padding(alphabet, data, padding);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / wrap.width.max(1) * 2);
+    wrap.wrap_into(&encoded, &mut 0, &mut out);
+    out
+}
+
+fn encode_chunk<T: Alphabet>(alphabet: &T, chunk: Vec<u8>, padding: Padding) -> Vec<char> {
+    //map every index of the chunk to its char
+    let mut out = chunk
+        .iter()
+        .filter_map(|index| alphabet.get_char_for_index(*index))
+        .collect::<Vec<char>>();
+    //fill the rest of the 4 characters with padding, unless it's not wanted or the alphabet has none
+    if let Some(padding_char) = alphabet.get_optional_padding_char() {
+        if padding != Padding::NoPad {
+            out.resize(4, padding_char);
         }
     }
     out
@@ -139,53 +406,139 @@ pub fn encode(data: &[u8]) -> String {
     encode_using_alphabet(classic_alphabet, data)
 }
 
+pub fn encode_url_safe(data: &[u8]) -> String {
+    let url_safe_alphabet = &UrlSafe {};
+    encode_using_alphabet(url_safe_alphabet, data)
+}
+
 
 //decoding
-pub fn decode_using_alphabet<T:Alphabet>(alphabet:T, data:&String)->Result<Vec<u8>, std::io::Error>{
-    // if data is not multiple of four bytes, data is invalid
-    if data.chars().count() % 4 != 0 {
-        return Err(std::io::Error::from(std::io::ErrorKind::InvalidInput))
+pub fn decode_using_alphabet<T:Alphabet>(alphabet:T, data:&str)->Result<Vec<u8>, DecodeError>{
+    decode_with_padding(alphabet, data, Padding::Pad)
+}
+
+pub fn decode_with_padding<T: Alphabet>(
+    alphabet: T,
+    data: &str,
+    padding: Padding,
+) -> Result<Vec<u8>, DecodeError> {
+    decode_with_strictness(alphabet, data, padding, Strictness::Lenient)
+}
+
+// Forgiving has its own padding rule, so the padding policy is ignored in that mode
+pub fn decode_with_strictness<T: Alphabet>(
+    alphabet: T,
+    data: &str,
+    padding: Padding,
+    strictness: Strictness,
+) -> Result<Vec<u8>, DecodeError> {
+    // every char keeps its byte offset so errors can point back into the input.
+    // Whitespace, like the line breaks of wrapped output, is not part of the encoding and skipped,
+    // unless decoding strictly where it would be a second way to write the same bytes
+    let mut chars = Vec::with_capacity(data.len());
+    for (offset, character) in data.char_indices() {
+        if !character.is_ascii_whitespace() {
+            chars.push((offset, character));
+        } else if strictness == Strictness::Strict {
+            return Err(DecodeError::InvalidByte { offset, byte: character as u8 });
+        }
+    }
+    decode_chars(&alphabet, &chars, padding, strictness)
+}
+
+// decodes the chars of a complete input, or the end of one that started with whole quanta
+fn decode_chars<T: Alphabet>(
+    alphabet: &T,
+    chars: &[(usize, char)],
+    padding: Padding,
+    strictness: Strictness,
+) -> Result<Vec<u8>, DecodeError> {
+    let padding = match strictness {
+        Strictness::Forgiving => Padding::Indifferent,
+        _ => padding,
     }
+    .for_alphabet(alphabet);
+
+    // the padding can only be at the end of the input, so we count it from there
+    let padding_char = alphabet.get_optional_padding_char();
+    let unpadded_len = chars
+        .iter()
+        .rposition(|(_, character)| Some(*character) != padding_char)
+        .map_or(0, |position| position + 1);
+    let pad_len = chars.len() - unpadded_len;
+
+    // if the left over characters can't form a quantum, or the padding doesn't match the policy, data is invalid
+    padding.check(unpadded_len, pad_len)?;
 
-    // we split the string into its chars and slice it in chunks of 4 char's.
+    // we slice the unpadded chars in chunks of 4 char's, the last one holding 2 to 4 of them.
     // Each slice is fed through the original function that will fetch the original 
-    // char from the alphabet which is flat_map'ed through the stitch function
-    let result = data
-        .chars()
-        .collect::<Vec<char>>()
-        .chunks(4)
-        .map(|chunk| original(&alphabet, chunk) )
-        .flat_map(stitch)
-        .collect();
+    // char from the alphabet which is then run through the stitch function
+    let mut result = Vec::with_capacity(unpadded_len / 4 * 3 + 2);
+    for chunk in chars[..unpadded_len].chunks(4) {
+        let indices = original(alphabet, chunk, strictness)?;
+        // only the last chunk can be short, its left over bits have to be zero to be canonical
+        if strictness == Strictness::Strict && !has_clean_tail(&indices) {
+            let (offset, character) = chunk[chunk.len() - 1];
+            return Err(DecodeError::InvalidLastSymbol { offset, byte: first_byte(character) });
+        }
+        result.extend(stitch(indices));
+    }
 
     Ok(result)
 }
 
-fn original<T: Alphabet>(alphabet: &T, chunk: &[char]) -> Vec<u8> {
+// 2 indices hold 1 byte and 4 bits too many, 3 indices hold 2 bytes and 2 bits too many
+fn has_clean_tail(indices: &[u8]) -> bool {
+    match indices.len() {
+        2 => indices[1] & 0b00001111 == 0,
+        3 => indices[2] & 0b00000011 == 0,
+        _ => true,
+    }
+}
+
+fn original<T: Alphabet>(
+    alphabet: &T,
+    chunk: &[(usize, char)],
+    strictness: Strictness,
+) -> Result<Vec<u8>, DecodeError> {
+    let padding_char = alphabet.get_optional_padding_char();
+    // padding in the middle of the input is only skipped when decoding leniently
+    if strictness != Strictness::Lenient {
+        let mut padding = chunk.iter().filter(|(_, character)| Some(*character) == padding_char);
+        if let Some(&(offset, character)) = padding.next() {
+            return Err(DecodeError::InvalidByte { offset, byte: first_byte(character) });
+        }
+    }
+
     //It filters the padding characters and uses the looks up the left-over characters in our alphabet
-    chunk
+    let indices = chunk
         .iter()
-        .filter(|character| *character != &alphabet.get_padding_char())
-        .map(|character| { 
+        .filter(|(_, character)| Some(*character) != padding_char)
+        .map(|&(offset, character)| {
             alphabet
-                .get_index_for_char(*character)
-                .expect("unable to find character in alphabet")
+                .get_index_for_char(character)
+                .ok_or(DecodeError::InvalidByte { offset, byte: first_byte(character) })
         })
-        .collect()
+        .collect::<Result<Vec<u8>, DecodeError>>()?;
+
+    // padding in the middle of the input can leave a chunk with too few characters to hold a byte
+    if indices.len() < 2 {
+        return Err(DecodeError::InvalidPadding);
+    }
+    Ok(indices)
 }
 
-//It takes a Vec of bytes and returns another Vec of bytes, containing a maximum of three 8-bit numbers.
-fn stitch(bytes: Vec<u8>) -> Vec<u8> {
-    let out = match bytes.len() {
-        2 => vec![
-            (bytes[0] & 0b00111111) << 2 | bytes[1] >> 4,
-            (bytes[1] & 0b00001111) << 4,
-        ],
+//It takes a Vec of 6-bit indices and returns another Vec of bytes, containing a maximum of three 8-bit numbers.
+// n indices carry n * 6 bits, of which only the first n - 1 whole bytes are data. The bits left over
+// in the last index are the zeroes the encoder padded the final byte with, so they are dropped.
+// Every byte we return is real data, a 0x00 included.
+pub(crate) fn stitch(bytes: Vec<u8>) -> Vec<u8> {
+    match bytes.len() {
+        2 => vec![(bytes[0] & 0b00111111) << 2 | bytes[1] >> 4],
 
         3 => vec![
             (bytes[0] & 0b00111111) << 2 | bytes[1] >> 4,
             (bytes[1] & 0b00001111) << 4 | bytes[2] >> 2,
-            (bytes[2] & 0b00000011) << 6,
         ],
 
         4 => vec![
@@ -195,12 +548,219 @@ fn stitch(bytes: Vec<u8>) -> Vec<u8> {
         ],
 
         _ => unreachable!()
-    };
-
-    out.into_iter().filter(|&x| x > 0).collect()
+    }
 }
 
-pub fn decode(bytes: &String) -> Result<Vec<u8>, std::io::Error> {
+pub fn decode(bytes: &str) -> Result<Vec<u8>, DecodeError> {
     let alphabet = Classic {};
     decode_using_alphabet(alphabet, bytes)
+}
+
+pub fn decode_url_safe(bytes: &str) -> Result<Vec<u8>, DecodeError> {
+    let alphabet = UrlSafe {};
+    decode_using_alphabet(alphabet, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 section 10 test vectors, they don't touch index 62 or 63
+    // so both alphabets must produce the same output
+    const RFC_4648_VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn classic_rfc_4648_vectors() {
+        for (plain, encoded) in RFC_4648_VECTORS.iter() {
+            assert_eq!(encode(plain.as_bytes()), *encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn url_safe_rfc_4648_vectors() {
+        for (plain, encoded) in RFC_4648_VECTORS.iter() {
+            assert_eq!(encode_url_safe(plain.as_bytes()), *encoded);
+            assert_eq!(decode_url_safe(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn url_safe_replaces_plus_and_slash() {
+        let data = [0xfb, 0xff, 0xbf];
+        assert_eq!(encode(&data), "+/+/");
+        assert_eq!(encode_url_safe(&data), "-_-_");
+        assert_eq!(decode_url_safe("-_-_").unwrap(), data);
+    }
+
+    #[test]
+    fn encode_without_padding() {
+        for (plain, encoded) in RFC_4648_VECTORS.iter() {
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(encode_with_padding(&Classic, plain.as_bytes(), Padding::NoPad), unpadded);
+            assert_eq!(encode_with_padding(&Classic, plain.as_bytes(), Padding::Indifferent), *encoded);
+        }
+        assert_eq!(encode_with_padding(&UrlSafe, &[0xfb, 0xff], Padding::NoPad), "-_8");
+    }
+
+    #[test]
+    fn decode_padding_policies() {
+        for (plain, encoded) in RFC_4648_VECTORS.iter() {
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(decode_with_padding(Classic, unpadded, Padding::NoPad).unwrap(), plain.as_bytes());
+            assert_eq!(decode_with_padding(Classic, unpadded, Padding::Indifferent).unwrap(), plain.as_bytes());
+            assert_eq!(decode_with_padding(Classic, encoded, Padding::Indifferent).unwrap(), plain.as_bytes());
+            assert_eq!(decode_with_padding(Classic, encoded, Padding::Pad).unwrap(), plain.as_bytes());
+        }
+        assert_eq!(decode_with_padding(UrlSafe, "-_8", Padding::NoPad).unwrap(), [0xfb, 0xff]);
+
+        // padding where none is allowed, and missing where it is required
+        assert!(decode_with_padding(Classic, "Zg==", Padding::NoPad).is_err());
+        assert!(decode_with_padding(Classic, "Zg", Padding::Pad).is_err());
+        // partial or excess padding is never valid
+        assert!(decode_with_padding(Classic, "Zg=", Padding::Indifferent).is_err());
+        assert!(decode_with_padding(Classic, "Zm9v=", Padding::Indifferent).is_err());
+        // a lone character left over can't hold a byte
+        assert!(decode_with_padding(Classic, "Zm9vY", Padding::NoPad).is_err());
+        assert!(decode_with_padding(Classic, "Zm9vY===", Padding::Indifferent).is_err());
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode("Zm9v!mFy"), Err(DecodeError::InvalidByte { offset: 4, byte: b'!' }));
+        assert_eq!(decode_url_safe("Zm9v+mFy"), Err(DecodeError::InvalidByte { offset: 4, byte: b'+' }));
+        // offsets are in bytes, and non-ascii characters never alias an ascii one
+        assert_eq!(decode("ém9v"), Err(DecodeError::InvalidByte { offset: 0, byte: 0xc3 }));
+        assert_eq!(decode("Zm\u{141}v"), Err(DecodeError::InvalidByte { offset: 2, byte: 0xc5 }));
+        assert_eq!(decode("Zm9vY"), Err(DecodeError::InvalidLength));
+        assert_eq!(decode("Zm9vYg="), Err(DecodeError::InvalidPadding));
+        assert_eq!(decode("Zg==="), Err(DecodeError::InvalidPadding));
+        assert_eq!(decode("A===AAAA"), Err(DecodeError::InvalidPadding));
+        assert_eq!(decode("====AAAA"), Err(DecodeError::InvalidPadding));
+
+        // moving an error into a larger text only touches the errors that have an offset
+        assert_eq!(
+            DecodeError::InvalidLastSymbol { offset: 2, byte: b'h' }.shifted(10),
+            DecodeError::InvalidLastSymbol { offset: 12, byte: b'h' }
+        );
+        assert_eq!(DecodeError::InvalidPadding.shifted(10), DecodeError::InvalidPadding);
+
+        assert_eq!(
+            DecodeError::InvalidByte { offset: 4, byte: b'!' }.to_string(),
+            "invalid byte 0x21 at offset 4"
+        );
+    }
+
+    #[test]
+    fn wrapped_lines() {
+        let data = [0xab; 120];
+        let mime = encode_wrapped(&Classic, &data, Padding::Pad, LineWrap::MIME);
+        let lines = mime.split("\r\n").collect::<Vec<&str>>();
+        assert_eq!(lines.iter().map(|line| line.len()).collect::<Vec<usize>>(), [76, 76, 8]);
+        assert!(!mime.ends_with('\n'));
+
+        let pem = encode_wrapped(&Classic, &data, Padding::Pad, LineWrap::PEM);
+        let lines = pem.split('\n').collect::<Vec<&str>>();
+        assert_eq!(lines.iter().map(|line| line.len()).collect::<Vec<usize>>(), [64, 64, 32]);
+        assert_eq!(lines.concat(), encode(&data));
+
+        // exactly one line, and no wrapping at all
+        assert_eq!(encode_wrapped(&Classic, &[0; 48], Padding::Pad, LineWrap::PEM), encode(&[0; 48]));
+        let unwrapped = LineWrap { width: 0, ending: LineEnding::Lf };
+        assert_eq!(encode_wrapped(&Classic, &data, Padding::Pad, unwrapped), encode(&data));
+    }
+
+    #[test]
+    fn decode_skips_whitespace() {
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        let mime = encode_wrapped(&Classic, &data, Padding::Pad, LineWrap::MIME);
+        assert_eq!(decode(&mime).unwrap(), data);
+        assert_eq!(decode(" Zm9v\tYg\n==\r\n").unwrap(), b"foob");
+
+        // offsets still count the skipped characters
+        assert_eq!(decode("Zm9v\r\nYm!y"), Err(DecodeError::InvalidByte { offset: 8, byte: b'!' }));
+    }
+
+    #[test]
+    fn strict_decoding() {
+        let strict = |data: &str| decode_with_strictness(Classic, data, Padding::Pad, Strictness::Strict);
+        for (plain, encoded) in RFC_4648_VECTORS.iter() {
+            assert_eq!(strict(encoded).unwrap(), plain.as_bytes());
+        }
+
+        // QR== carries the same byte as QQ== but with a stray bit set
+        assert_eq!(decode("QR==").unwrap(), b"A");
+        assert_eq!(strict("QQ==").unwrap(), b"A");
+        assert_eq!(strict("QR=="), Err(DecodeError::InvalidLastSymbol { offset: 1, byte: b'R' }));
+        assert_eq!(strict("Zm9vYmF="), Err(DecodeError::InvalidLastSymbol { offset: 6, byte: b'F' }));
+
+        // padding in the middle is skipped leniently, but not here
+        assert_eq!(decode("Zg==Zg==").unwrap(), b"ff");
+        assert_eq!(strict("Zg==Zg=="), Err(DecodeError::InvalidByte { offset: 2, byte: b'=' }));
+
+        // the padding policy still applies, and a foreign padding character is just an invalid byte
+        assert_eq!(
+            decode_with_strictness(Classic, "Zg", Padding::NoPad, Strictness::Strict).unwrap(),
+            b"f"
+        );
+        assert_eq!(strict("Zg"), Err(DecodeError::InvalidPadding));
+        assert_eq!(strict("Zg.."), Err(DecodeError::InvalidByte { offset: 2, byte: b'.' }));
+
+        // whitespace would be yet another way to write the same bytes
+        assert_eq!(decode("Q Q==").unwrap(), b"A");
+        assert_eq!(strict("Q Q=="), Err(DecodeError::InvalidByte { offset: 1, byte: b' ' }));
+        assert_eq!(strict("QQ\n=="), Err(DecodeError::InvalidByte { offset: 2, byte: b'\n' }));
+        assert_eq!(strict("QQ==\r\n"), Err(DecodeError::InvalidByte { offset: 4, byte: b'\r' }));
+    }
+
+    #[test]
+    fn forgiving_decoding() {
+        // whatever the padding policy, forgiving decodes the way atob does
+        let forgiving =
+            |data: &str| decode_with_strictness(Classic, data, Padding::NoPad, Strictness::Forgiving);
+        assert_eq!(forgiving("Zm9vYg==").unwrap(), b"foob");
+        assert_eq!(forgiving(" Zm9v Yg\n").unwrap(), b"foob");
+        assert_eq!(forgiving("QR==").unwrap(), b"A");
+        assert_eq!(forgiving("QR").unwrap(), b"A");
+
+        assert_eq!(forgiving("Zg="), Err(DecodeError::InvalidPadding));
+        assert_eq!(forgiving("Zm9vY"), Err(DecodeError::InvalidLength));
+        assert_eq!(forgiving("Zg==Zg=="), Err(DecodeError::InvalidByte { offset: 2, byte: b'=' }));
+        assert_eq!(forgiving("Zm-v"), Err(DecodeError::InvalidByte { offset: 2, byte: b'-' }));
+    }
+
+    #[test]
+    fn zero_bytes_are_kept() {
+        assert_eq!(encode(&[0, 1, 0]), "AAEA");
+        assert_eq!(decode(&encode(&[0, 1, 0])).unwrap(), [0, 1, 0]);
+        assert_eq!(decode("AA==").unwrap(), [0]);
+        assert_eq!(decode("AAA=").unwrap(), [0, 0]);
+    }
+
+    #[test]
+    fn round_trip_all_bytes_and_lengths() {
+        for len in 0..=64 {
+            for byte in 0..=255_u8 {
+                // a run of the same byte, and a run counting up from it so every position sees every value
+                let same = vec![byte; len];
+                let counting = (0..len).map(|i| byte.wrapping_add(i as u8)).collect::<Vec<u8>>();
+
+                for data in [same, counting].iter() {
+                    assert_eq!(decode(&encode(data)).unwrap(), *data);
+                    assert_eq!(decode_url_safe(&encode_url_safe(data)).unwrap(), *data);
+
+                    let unpadded = encode_with_padding(&Classic, data, Padding::NoPad);
+                    assert_eq!(decode_with_padding(Classic, &unpadded, Padding::NoPad).unwrap(), *data);
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
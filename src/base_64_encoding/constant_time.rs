@@ -0,0 +1,216 @@
+// Base64 for secrets like private keys, where how long encoding takes must not depend on the data.
+// The alphabets branch on the value they look up and the lookup tables index memory with it,
+// both of which can be timed. Here a symbol is computed with arithmetic only: every range check
+// turns into a mask that is all ones or all zeroes, and the masks pick the offset to add.
+// Only the standard and URL-safe alphabets work this way, they differ in the characters for 62 and 63.
+//
+// The length of the input and its padding aren't secret, so the work depends on those.
+// An invalid character doesn't stop the decoder, it's only looked for once the whole input is decoded.
+// Unlike decode_with_padding, whitespace and padding characters in the middle of the input are invalid:
+// strip line breaks first (where they are isn't secret either).
+use super::{Alphabet, Classic, DecodeError, Padding, UrlSafe};
+
+// Letters and digits in the standard order, with these for 62 and 63
+pub trait ConstantTimeAlphabet: Alphabet {
+    const SYMBOL_62: u8;
+    const SYMBOL_63: u8;
+}
+
+impl ConstantTimeAlphabet for Classic {
+    const SYMBOL_62: u8 = b'+';
+    const SYMBOL_63: u8 = b'/';
+}
+
+impl ConstantTimeAlphabet for UrlSafe {
+    const SYMBOL_62: u8 = b'-';
+    const SYMBOL_63: u8 = b'_';
+}
+
+// -1 (all ones) when x is negative, 0 otherwise, for x in -256..256
+fn negative_mask(x: i16) -> i16 {
+    x >> 8
+}
+
+// -1 when low <= c <= high, 0 otherwise
+fn range_mask(c: i16, low: u8, high: u8) -> i16 {
+    negative_mask((low as i16 - 1 - c) & (c - high as i16 - 1))
+}
+
+// Starts from 'A' + index and adds the distance to the next range for every range boundary index is past
+fn encode_symbol<T: ConstantTimeAlphabet>(index: u8) -> u8 {
+    let index = index as i16;
+    let mut symbol = index + b'A' as i16;
+    // 'a' comes 6 after the end of A-Z
+    symbol += negative_mask(25 - index) & 6;
+    // '0' comes 75 before the end of a-z
+    symbol -= negative_mask(51 - index) & 75;
+    symbol += negative_mask(61 - index) & (T::SYMBOL_62 as i16 - b'0' as i16 - 10);
+    symbol += negative_mask(62 - index) & (T::SYMBOL_63 as i16 - T::SYMBOL_62 as i16 - 1);
+    symbol as u8
+}
+
+// The index of symbol, or -1 if it isn't in the alphabet.
+// Each range adds index + 1 when symbol is in it, and only one range can hold it
+fn decode_symbol<T: ConstantTimeAlphabet>(symbol: u8) -> i16 {
+    let c = symbol as i16;
+    let mut index = -1;
+    index += range_mask(c, b'A', b'Z') & (c - b'A' as i16 + 1);
+    index += range_mask(c, b'a', b'z') & (c - b'a' as i16 + 27);
+    index += range_mask(c, b'0', b'9') & (c - b'0' as i16 + 53);
+    index += range_mask(c, T::SYMBOL_62, T::SYMBOL_62) & 63;
+    index += range_mask(c, T::SYMBOL_63, T::SYMBOL_63) & 64;
+    index
+}
+
+pub fn encode_constant_time<T: ConstantTimeAlphabet>(
+    _alphabet: &T,
+    data: &[u8],
+    padding: Padding,
+) -> String {
+    let mut out = Vec::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut group = [0; 4];
+        group[1..=chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes(group);
+        // n bytes need n + 1 symbols
+        for position in 0..=chunk.len() {
+            out.push(encode_symbol::<T>(
+                (bits >> (18 - 6 * position) & 0x3f) as u8,
+            ));
+        }
+        if padding != Padding::NoPad {
+            out.resize(out.len() + 3 - chunk.len(), b'=');
+        }
+    }
+    String::from_utf8(out).expect("symbols are ascii")
+}
+
+// The same output and errors as decode_with_padding for input without whitespace or padding in the middle
+pub fn decode_constant_time<T: ConstantTimeAlphabet>(
+    alphabet: &T,
+    data: &str,
+    padding: Padding,
+) -> Result<Vec<u8>, DecodeError> {
+    let bytes = data.as_bytes();
+    let unpadded_len = bytes
+        .iter()
+        .rposition(|&byte| byte != b'=')
+        .map_or(0, |position| position + 1);
+    // lengths count characters like the normal codec does, only input that is invalid anyway has more bytes
+    padding.for_alphabet(alphabet).check(
+        data[..unpadded_len].chars().count(),
+        bytes.len() - unpadded_len,
+    )?;
+
+    let unpadded = &bytes[..unpadded_len];
+    let mut out = Vec::with_capacity(unpadded_len / 4 * 3 + 2);
+    // any invalid symbol leaves its sign bit here
+    let mut invalid = 0;
+    for quantum in unpadded.chunks(4) {
+        let mut bits = 0_u32;
+        for (position, &symbol) in quantum.iter().enumerate() {
+            let index = decode_symbol::<T>(symbol);
+            invalid |= index;
+            bits |= ((index & 0x3f) as u32) << (18 - 6 * position);
+        }
+        // n symbols carry n - 1 whole bytes, the bits left over are ignored
+        out.extend_from_slice(&bits.to_be_bytes()[1..quantum.len()]);
+    }
+
+    if invalid < 0 {
+        let offset = unpadded
+            .iter()
+            .position(|&symbol| decode_symbol::<T>(symbol) < 0)
+            .expect("an invalid symbol was seen");
+        return Err(DecodeError::InvalidByte {
+            offset,
+            byte: bytes[offset],
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode_with_padding, encode_with_padding};
+    use super::*;
+    use crate::test_util::Lcg;
+
+    #[test]
+    fn symbols_match_the_alphabets() {
+        for index in 0..64 {
+            assert_eq!(
+                Some(encode_symbol::<Classic>(index) as char),
+                Classic.get_char_for_index(index)
+            );
+            assert_eq!(
+                Some(encode_symbol::<UrlSafe>(index) as char),
+                UrlSafe.get_char_for_index(index)
+            );
+        }
+        for byte in 0..=255_u8 {
+            let expected = |index: Option<u8>| index.map_or(-1, |index| index as i16);
+            assert_eq!(
+                decode_symbol::<Classic>(byte),
+                expected(Classic.get_index_for_char(byte as char))
+            );
+            assert_eq!(
+                decode_symbol::<UrlSafe>(byte),
+                expected(UrlSafe.get_index_for_char(byte as char))
+            );
+        }
+    }
+
+    #[test]
+    fn matches_the_normal_codec() {
+        let data = Lcg::new(0x2545f491).bytes(300);
+
+        for len in 0..data.len() {
+            let data = &data[..len];
+            for &padding in [Padding::Pad, Padding::NoPad].iter() {
+                let classic = encode_with_padding(&Classic, data, padding);
+                assert_eq!(encode_constant_time(&Classic, data, padding), classic);
+                assert_eq!(
+                    decode_constant_time(&Classic, &classic, padding),
+                    decode_with_padding(Classic, &classic, padding)
+                );
+
+                let url_safe = encode_with_padding(&UrlSafe, data, padding);
+                assert_eq!(encode_constant_time(&UrlSafe, data, padding), url_safe);
+                assert_eq!(
+                    decode_constant_time(&UrlSafe, &url_safe, Padding::Indifferent),
+                    decode_with_padding(UrlSafe, &url_safe, Padding::Indifferent)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn errors_match_the_normal_codec() {
+        for input in [
+            "Zm9v*mFy",
+            "Zm9vY",
+            "Zm9vYg",
+            "Zm9vYg===",
+            "Zm9vYg=",
+            "Zm/vYg==",
+            "Zm9vé",
+            "QR==",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                decode_constant_time(&Classic, input, Padding::Pad),
+                decode_with_padding(Classic, input, Padding::Pad),
+                "{}",
+                input
+            );
+            assert_eq!(
+                decode_constant_time(&UrlSafe, input, Padding::Indifferent),
+                decode_with_padding(UrlSafe, input, Padding::Indifferent),
+                "{}",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,252 @@
+// An alphabet made from a string of 64 symbols, so new alphabets don't need their own offset arithmetic
+// the way Classic does. The string is checked once, then both directions are plain table lookups:
+// symbols maps an index to its character and indices maps an ascii character back to its index.
+use super::Alphabet;
+use std::fmt;
+
+// marks the characters of the indices table that are not part of the alphabet
+const NOT_A_SYMBOL: u8 = 0xff;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomAlphabet {
+    symbols: [u8; 64],
+    indices: [u8; 128],
+    padding: Option<char>,
+}
+
+// crypt(3) orders the digits before the letters
+pub const CRYPT: CustomAlphabet = CustomAlphabet::from_symbols(
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+    None,
+);
+
+// bcrypt uses the same characters as crypt, but with the letters first
+pub const BCRYPT: CustomAlphabet = CustomAlphabet::from_symbols(
+    b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+    None,
+);
+
+// the modified Base64 of IMAP mailbox names (RFC 3501), ',' takes the place of '/' and there is no padding
+pub const IMAP_MAILBOX: CustomAlphabet = CustomAlphabet::from_symbols(
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+,",
+    None,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphabetError {
+    // there must be exactly 64 symbols
+    InvalidLength(usize),
+    // symbols and padding must be printable ascii, whitespace is skipped when decoding so it can't be one
+    NotPrintableAscii(char),
+    DuplicateSymbol(char),
+    PaddingIsSymbol(char),
+}
+
+impl fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlphabetError::InvalidLength(len) => {
+                write!(f, "alphabet has {} symbols instead of 64", len)
+            }
+            AlphabetError::NotPrintableAscii(character) => {
+                write!(f, "{:?} is not a printable ascii character", character)
+            }
+            AlphabetError::DuplicateSymbol(character) => {
+                write!(f, "{:?} is in the alphabet twice", character)
+            }
+            AlphabetError::PaddingIsSymbol(character) => {
+                write!(
+                    f,
+                    "padding {:?} is also a symbol of the alphabet",
+                    character
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AlphabetError {}
+
+impl CustomAlphabet {
+    pub fn new(symbols: &str, padding: Option<char>) -> Result<CustomAlphabet, AlphabetError> {
+        let len = symbols.chars().count();
+        if len != 64 {
+            return Err(AlphabetError::InvalidLength(len));
+        }
+
+        let mut seen = [false; 128];
+        for character in symbols.chars() {
+            if !character.is_ascii_graphic() {
+                return Err(AlphabetError::NotPrintableAscii(character));
+            }
+            if seen[character as usize] {
+                return Err(AlphabetError::DuplicateSymbol(character));
+            }
+            seen[character as usize] = true;
+        }
+
+        if let Some(padding) = padding {
+            if !padding.is_ascii_graphic() {
+                return Err(AlphabetError::NotPrintableAscii(padding));
+            }
+            if seen[padding as usize] {
+                return Err(AlphabetError::PaddingIsSymbol(padding));
+            }
+        }
+
+        let mut table = [0; 64];
+        table.copy_from_slice(symbols.as_bytes());
+        Ok(CustomAlphabet::from_symbols(&table, padding))
+    }
+
+    // builds both tables without any checks, new() and the constants above make sure the symbols are valid
+    const fn from_symbols(symbols: &[u8; 64], padding: Option<char>) -> CustomAlphabet {
+        let mut indices = [NOT_A_SYMBOL; 128];
+        let mut index = 0;
+        while index < 64 {
+            indices[symbols[index] as usize] = index as u8;
+            index += 1;
+        }
+        CustomAlphabet {
+            symbols: *symbols,
+            indices,
+            padding,
+        }
+    }
+}
+
+impl Alphabet for CustomAlphabet {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        self.symbols
+            .get(index as usize)
+            .map(|&symbol| symbol as char)
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        if !character.is_ascii() {
+            return None;
+        }
+        match self.indices[character as usize] {
+            NOT_A_SYMBOL => None,
+            index => Some(index),
+        }
+    }
+
+    // without padding this is never used, see get_optional_padding_char
+    fn get_padding_char(&self) -> char {
+        self.padding.unwrap_or('=')
+    }
+
+    fn get_optional_padding_char(&self) -> Option<char> {
+        self.padding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        decode, decode_with_padding, encode, encode_with_padding, Classic, Padding, UrlSafe,
+    };
+    use super::*;
+
+    const CLASSIC_SYMBOLS: &str =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    #[test]
+    fn behaves_like_the_builtin_alphabets() {
+        let classic = CustomAlphabet::new(CLASSIC_SYMBOLS, Some('=')).unwrap();
+        let url_safe =
+            CustomAlphabet::new(&CLASSIC_SYMBOLS.replace("+/", "-_"), Some('=')).unwrap();
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        for len in 0..=10 {
+            assert_eq!(
+                encode_with_padding(&classic, &data[..len], Padding::Pad),
+                encode(&data[..len])
+            );
+            assert_eq!(
+                encode_with_padding(&url_safe, &data[..len], Padding::Pad),
+                encode_with_padding(&UrlSafe, &data[..len], Padding::Pad)
+            );
+        }
+        let encoded = encode(&data);
+        assert_eq!(
+            decode_with_padding(classic, &encoded, Padding::Pad),
+            decode(&encoded)
+        );
+    }
+
+    #[test]
+    fn predefined_alphabets() {
+        assert_eq!(
+            CRYPT,
+            CustomAlphabet::new(
+                "./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+                None
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            BCRYPT,
+            CustomAlphabet::new(
+                "./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                None
+            )
+            .unwrap()
+        );
+
+        // no padding, so the Pad policy writes none and doesn't require any
+        assert_eq!(BCRYPT.get_optional_padding_char(), None);
+        assert_eq!(
+            Classic.get_optional_padding_char(),
+            Some(Classic.get_padding_char())
+        );
+        let encoded = encode_with_padding(&BCRYPT, &[0xff, 0xff], Padding::Pad);
+        assert_eq!(encoded, "996");
+        assert_eq!(
+            decode_with_padding(BCRYPT, &encoded, Padding::Pad).unwrap(),
+            [0xff, 0xff]
+        );
+        assert_eq!(
+            encode_with_padding(&CRYPT, b"\x00\x10\x83", Padding::Pad),
+            "./01"
+        );
+
+        assert_eq!(
+            encode_with_padding(&IMAP_MAILBOX, &[0xff, 0xfe], Padding::Pad),
+            ",,4"
+        );
+        assert_eq!(
+            decode_with_padding(IMAP_MAILBOX, ",,4", Padding::Pad).unwrap(),
+            [0xff, 0xfe]
+        );
+        assert!(decode_with_padding(IMAP_MAILBOX, "//4", Padding::Pad).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_alphabets() {
+        assert_eq!(
+            CustomAlphabet::new("abc", None),
+            Err(AlphabetError::InvalidLength(3))
+        );
+        assert_eq!(
+            CustomAlphabet::new(&CLASSIC_SYMBOLS.replace('+', "/"), None),
+            Err(AlphabetError::DuplicateSymbol('/'))
+        );
+        assert_eq!(
+            CustomAlphabet::new(&CLASSIC_SYMBOLS.replace('+', " "), None),
+            Err(AlphabetError::NotPrintableAscii(' '))
+        );
+        assert_eq!(
+            CustomAlphabet::new(&CLASSIC_SYMBOLS.replace('+', "\u{e9}"), None),
+            Err(AlphabetError::NotPrintableAscii('\u{e9}'))
+        );
+        assert_eq!(
+            CustomAlphabet::new(CLASSIC_SYMBOLS, Some('/')),
+            Err(AlphabetError::PaddingIsSymbol('/'))
+        );
+        assert_eq!(
+            CustomAlphabet::new(CLASSIC_SYMBOLS, Some('\n')),
+            Err(AlphabetError::NotPrintableAscii('\n'))
+        );
+    }
+}
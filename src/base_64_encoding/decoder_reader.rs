@@ -0,0 +1,249 @@
+// A streaming decoder: reading from it pulls Base64 from the inner reader and hands out the decoded bytes.
+// Characters are decoded one 4-character quantum at a time, whatever doesn't fill a quantum yet is kept
+// for the next read. The last quantum is held back until the inner reader is exhausted, since only then
+// do we know whether its padding (or the lack of it) is where the stream ends.
+// Errors are returned as io::Error of kind InvalidData wrapping a DecodeError, whose offsets
+// count from the start of the stream.
+use super::{decode_chars, original, stitch, Alphabet, DecodeError, Padding, Strictness};
+use crate::io_util::invalid_data;
+use std::io::{self, Read};
+
+const BUFFER_SIZE: usize = 1024;
+
+pub struct DecoderReader<R: Read, A: Alphabet> {
+    inner: R,
+    alphabet: A,
+    padding: Padding,
+    strictness: Strictness,
+    // encoded characters not decoded yet, along with their offset in the stream
+    encoded: Vec<(usize, char)>,
+    // offset of the next byte we get from the inner reader
+    offset: usize,
+    // decoded bytes waiting to be read, starting at decoded_pos
+    decoded: Vec<u8>,
+    decoded_pos: usize,
+    finished: bool,
+}
+
+impl<R: Read, A: Alphabet> DecoderReader<R, A> {
+    pub fn new(inner: R, alphabet: A) -> Self {
+        DecoderReader::with_padding(inner, alphabet, Padding::Pad)
+    }
+
+    pub fn with_padding(inner: R, alphabet: A, padding: Padding) -> Self {
+        DecoderReader {
+            inner,
+            alphabet,
+            padding,
+            strictness: Strictness::Lenient,
+            encoded: Vec::new(),
+            offset: 0,
+            decoded: Vec::new(),
+            decoded_pos: 0,
+            finished: false,
+        }
+    }
+
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // reads the next piece of the stream and decodes every quantum we can be sure about
+    fn fill(&mut self) -> io::Result<()> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let read = self.inner.read(&mut buffer)?;
+
+        self.decoded.clear();
+        self.decoded_pos = 0;
+
+        // end of the stream, what's left is the last quantum and it has to follow the padding policy
+        if read == 0 {
+            self.finished = true;
+            self.decoded =
+                decode_chars(&self.alphabet, &self.encoded, self.padding, self.strictness)
+                    .map_err(invalid_data)?;
+            self.encoded.clear();
+            return Ok(());
+        }
+
+        for (i, &byte) in buffer[..read].iter().enumerate() {
+            let offset = self.offset + i;
+            // alphabets are ascii, and a multi-byte character could be split between two reads
+            if !byte.is_ascii() {
+                return Err(invalid_data(DecodeError::InvalidByte { offset, byte }));
+            }
+            // line breaks and other whitespace are not part of the encoding, Strict doesn't allow them
+            if !byte.is_ascii_whitespace() {
+                self.encoded.push((offset, byte as char));
+            } else if self.strictness == Strictness::Strict {
+                return Err(invalid_data(DecodeError::InvalidByte { offset, byte }));
+            }
+        }
+        self.offset += read;
+
+        // everything but the last (possibly incomplete) quantum can be decoded right away
+        let whole = self.encoded.len().saturating_sub(1) / 4 * 4;
+        for chunk in self.encoded[..whole].chunks(4) {
+            let indices = original(&self.alphabet, chunk, self.strictness).map_err(invalid_data)?;
+            self.decoded.extend(stitch(indices));
+        }
+        self.encoded.drain(..whole);
+        Ok(())
+    }
+}
+
+impl<R: Read, A: Alphabet> Read for DecoderReader<R, A> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // a read that decoded nothing (a lone quantum held back) is not the end, so we keep going
+        while self.decoded_pos == self.decoded.len() {
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let available = &self.decoded[self.decoded_pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.decoded_pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{encode, Classic, UrlSafe};
+    use super::*;
+    use crate::io_util::Trickle;
+
+    fn decode_stream<A: Alphabet>(
+        data: &[u8],
+        step: usize,
+        alphabet: A,
+        padding: Padding,
+    ) -> io::Result<Vec<u8>> {
+        let mut reader = DecoderReader::with_padding(Trickle { data, step }, alphabet, padding);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    fn decode_error(err: io::Error) -> DecodeError {
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        *err.into_inner().unwrap().downcast::<DecodeError>().unwrap()
+    }
+
+    #[test]
+    fn matches_one_shot_decoding() {
+        for len in [0, 1, 2, 3, 4, 100, 3000].iter() {
+            let data = (0..=255_u8).cycle().take(*len).collect::<Vec<u8>>();
+            let encoded = encode(&data);
+            for step in [1, 3, 4, 5, 4096].iter() {
+                assert_eq!(
+                    decode_stream(encoded.as_bytes(), *step, Classic, Padding::Pad).unwrap(),
+                    data
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn padding_at_the_end() {
+        assert_eq!(
+            decode_stream(b"Zm9vYg==", 1, Classic, Padding::Pad).unwrap(),
+            b"foob"
+        );
+        assert_eq!(
+            decode_stream(b"Zm9vYg", 1, UrlSafe, Padding::NoPad).unwrap(),
+            b"foob"
+        );
+        assert_eq!(
+            decode_stream(b"Zm9vYmE", 2, Classic, Padding::Indifferent).unwrap(),
+            b"fooba"
+        );
+
+        assert_eq!(
+            decode_stream(b"Zm9v\nYg=\n=\n", 1, Classic, Padding::Pad).unwrap(),
+            b"foob"
+        );
+
+        let err = decode_stream(b"Zm9vYg", 3, Classic, Padding::Pad).unwrap_err();
+        assert_eq!(decode_error(err), DecodeError::InvalidPadding);
+        let err = decode_stream(b"Zm9vYg===", 3, Classic, Padding::Pad).unwrap_err();
+        assert_eq!(decode_error(err), DecodeError::InvalidPadding);
+        let err = decode_stream(b"Zm9vY", 3, Classic, Padding::Pad).unwrap_err();
+        assert_eq!(decode_error(err), DecodeError::InvalidLength);
+    }
+
+    #[test]
+    fn strict_stream() {
+        let strict = |data: &'static [u8]| {
+            let reader = DecoderReader::new(Trickle { data, step: 3 }, Classic);
+            let mut reader = reader.with_strictness(Strictness::Strict);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).map(|_| out)
+        };
+        assert_eq!(strict(b"Zm9vQQ==").unwrap(), b"fooA");
+        let err = strict(b"Zm9vQR==").unwrap_err();
+        assert_eq!(
+            decode_error(err),
+            DecodeError::InvalidLastSymbol {
+                offset: 5,
+                byte: b'R'
+            }
+        );
+        let err = strict(b"Zg==Zm9v").unwrap_err();
+        assert_eq!(
+            decode_error(err),
+            DecodeError::InvalidByte {
+                offset: 2,
+                byte: b'='
+            }
+        );
+        let err = strict(b"Zm9v\nQQ==").unwrap_err();
+        assert_eq!(
+            decode_error(err),
+            DecodeError::InvalidByte {
+                offset: 4,
+                byte: b'\n'
+            }
+        );
+    }
+
+    #[test]
+    fn errors_report_stream_offsets() {
+        let mut encoded = encode(&[7; 3000]).into_bytes();
+        encoded[2001] = b'*';
+        let err = decode_stream(&encoded, 7, Classic, Padding::Pad).unwrap_err();
+        assert_eq!(
+            decode_error(err),
+            DecodeError::InvalidByte {
+                offset: 2001,
+                byte: b'*'
+            }
+        );
+
+        let err = decode_stream(b"Zm9v\r\nYm9v\r\n*AAA", 2, Classic, Padding::Pad).unwrap_err();
+        assert_eq!(
+            decode_error(err),
+            DecodeError::InvalidByte {
+                offset: 12,
+                byte: b'*'
+            }
+        );
+
+        let err = decode_stream("Zm9v\u{e9}".as_bytes(), 1, Classic, Padding::Pad).unwrap_err();
+        assert_eq!(
+            decode_error(err),
+            DecodeError::InvalidByte {
+                offset: 4,
+                byte: 0xc3
+            }
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// A streaming encoder: bytes written to it come out Base64 encoded on the inner writer.
+// Encoding only works on whole 3-byte groups, so whatever is left over from a write
+// (at most 2 bytes) is kept until the next write completes the group.
+// The last, possibly padded, quantum is written by finish() or when the encoder is dropped.
+// With a line wrap set the output is broken into lines as it goes, the column carrying over between writes.
+use super::{encode_with_padding, Alphabet, LineWrap, Padding};
+use std::io::{self, Write};
+
+pub struct EncoderWriter<W: Write, A: Alphabet> {
+    // None once finish() handed the writer back
+    inner: Option<W>,
+    alphabet: A,
+    padding: Padding,
+    // bytes waiting for a complete 3-byte group
+    pending: [u8; 3],
+    pending_len: usize,
+    wrap: Option<LineWrap>,
+    // characters written on the current line
+    column: usize,
+}
+
+impl<W: Write, A: Alphabet> EncoderWriter<W, A> {
+    pub fn new(inner: W, alphabet: A) -> Self {
+        EncoderWriter::with_padding(inner, alphabet, Padding::Pad)
+    }
+
+    pub fn with_padding(inner: W, alphabet: A, padding: Padding) -> Self {
+        EncoderWriter {
+            inner: Some(inner),
+            alphabet,
+            padding,
+            pending: [0; 3],
+            pending_len: 0,
+            wrap: None,
+            column: 0,
+        }
+    }
+
+    pub fn with_line_wrap(mut self, wrap: LineWrap) -> Self {
+        self.wrap = Some(wrap);
+        self
+    }
+
+    // writes the last quantum and returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_final_quantum()?;
+        let mut inner = self.inner.take().expect("writer is only taken by finish");
+        inner.flush()?;
+        Ok(inner)
+    }
+
+    fn write_final_quantum(&mut self) -> io::Result<()> {
+        if self.pending_len == 0 {
+            return Ok(());
+        }
+        let encoded = encode_with_padding(
+            &self.alphabet,
+            &self.pending[..self.pending_len],
+            self.padding,
+        );
+        self.write_encoded(&encoded)?;
+        self.pending_len = 0;
+        Ok(())
+    }
+
+    fn write_encoded(&mut self, encoded: &str) -> io::Result<()> {
+        let inner = match self.inner.as_mut() {
+            Some(inner) => inner,
+            None => return Err(io::Error::other("encoder already finished")),
+        };
+        match self.wrap {
+            Some(wrap) => {
+                let mut wrapped = String::with_capacity(encoded.len() * 2);
+                wrap.wrap_into(encoded, &mut self.column, &mut wrapped);
+                inner.write_all(wrapped.as_bytes())
+            }
+            None => inner.write_all(encoded.as_bytes()),
+        }
+    }
+}
+
+impl<W: Write, A: Alphabet> Write for EncoderWriter<W, A> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // top up the pending group first, if that doesn't complete it there is nothing to write yet
+        let missing = if self.pending_len > 0 {
+            3 - self.pending_len
+        } else {
+            0
+        };
+        if buf.len() < missing {
+            self.pending[self.pending_len..self.pending_len + buf.len()].copy_from_slice(buf);
+            self.pending_len += buf.len();
+            return Ok(buf.len());
+        }
+
+        let (head, rest) = buf.split_at(missing);
+        let whole = rest.len() / 3 * 3;
+        let (body, tail) = rest.split_at(whole);
+
+        // the completed pending group followed by all the whole groups of this write.
+        // Every group is full, so encode_with_padding never pads here.
+        let mut encoded = String::new();
+        if self.pending_len > 0 {
+            let mut group = self.pending;
+            group[self.pending_len..].copy_from_slice(head);
+            encoded.push_str(&encode_with_padding(&self.alphabet, &group, self.padding));
+        }
+        encoded.push_str(&encode_with_padding(&self.alphabet, body, self.padding));
+        self.write_encoded(&encoded)?;
+
+        // only now that the output made it through do we update what's pending
+        self.pending[..tail.len()].copy_from_slice(tail);
+        self.pending_len = tail.len();
+        Ok(buf.len())
+    }
+
+    // a partial group can't be flushed without padding it, so only the inner writer is flushed
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write, A: Alphabet> Drop for EncoderWriter<W, A> {
+    fn drop(&mut self) {
+        // errors can't be reported from drop, call finish() to see them
+        if !std::thread::panicking() {
+            let _ = self.write_final_quantum();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{encode, encode_url_safe, encode_wrapped, Classic, UrlSafe};
+    use super::*;
+
+    #[test]
+    fn matches_one_shot_encoding() {
+        let data = (0..=255_u8).cycle().take(1000).collect::<Vec<u8>>();
+        // every split of the input, from single bytes to bigger writes that cross groups
+        for piece in 1..=10 {
+            let mut writer = EncoderWriter::new(Vec::new(), Classic);
+            for chunk in data.chunks(piece) {
+                writer.write_all(chunk).unwrap();
+            }
+            let out = writer.finish().unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), encode(&data));
+        }
+    }
+
+    #[test]
+    fn pads_on_finish_and_drop() {
+        let mut writer = EncoderWriter::new(Vec::new(), UrlSafe);
+        writer.write_all(&[0xfb, 0xff, 0xbf, 0xfb]).unwrap();
+        assert_eq!(
+            writer.finish().unwrap(),
+            encode_url_safe(&[0xfb, 0xff, 0xbf, 0xfb]).as_bytes()
+        );
+
+        let mut out = Vec::new();
+        {
+            let mut writer = EncoderWriter::new(&mut out, Classic);
+            writer.write_all(b"fooba").unwrap();
+        }
+        assert_eq!(out, b"Zm9vYmE=");
+    }
+
+    #[test]
+    fn wraps_across_writes() {
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        let mut writer = EncoderWriter::new(Vec::new(), Classic).with_line_wrap(LineWrap::MIME);
+        for chunk in data.chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        let out = writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            encode_wrapped(&Classic, &data, Padding::Pad, LineWrap::MIME)
+        );
+    }
+
+    #[test]
+    fn unpadded() {
+        let mut writer = EncoderWriter::with_padding(Vec::new(), Classic, Padding::NoPad);
+        writer.write_all(b"f").unwrap();
+        writer.write_all(b"oob").unwrap();
+        assert_eq!(writer.finish().unwrap(), b"Zm9vYg");
+    }
+}
@@ -0,0 +1,195 @@
+// An Engine bundles every option of the codec, so they are picked once and then reused:
+// the alphabet, the padding policy, the line wrap for encoding and the strictness for decoding.
+// It's put together with the Config builder, in a const context too, which is how the
+// ready-made STANDARD, URL_SAFE and MIME engines are defined.
+use super::{
+    decode_with_strictness, encode_with_padding, Alphabet, Classic, DecodeError, LineWrap, Padding,
+    Strictness, UrlSafe,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config<A: Alphabet> {
+    alphabet: A,
+    padding: Padding,
+    wrap: Option<LineWrap>,
+    strictness: Strictness,
+}
+
+impl<A: Alphabet> Config<A> {
+    // padded, unwrapped and lenient, the same as encode and decode
+    pub const fn new(alphabet: A) -> Self {
+        Config {
+            alphabet,
+            padding: Padding::Pad,
+            wrap: None,
+            strictness: Strictness::Lenient,
+        }
+    }
+
+    pub const fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub const fn line_wrap(mut self, wrap: LineWrap) -> Self {
+        self.wrap = Some(wrap);
+        self
+    }
+
+    // Strict doesn't skip whitespace, so it can't read back what a line-wrapped engine wrote
+    pub const fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    pub const fn build(self) -> Engine<A> {
+        Engine { config: self }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Engine<A: Alphabet> {
+    config: Config<A>,
+}
+
+pub const STANDARD: Engine<Classic> = Config::new(Classic).build();
+pub const URL_SAFE: Engine<UrlSafe> = Config::new(UrlSafe).build();
+pub const MIME: Engine<Classic> = Config::new(Classic).line_wrap(LineWrap::MIME).build();
+
+impl<A: Alphabet> Engine<A> {
+    pub fn config(&self) -> &Config<A> {
+        &self.config
+    }
+
+    pub fn encode<T: AsRef<[u8]>>(&self, input: T) -> String {
+        let mut out = String::new();
+        self.encode_to_string(input, &mut out);
+        out
+    }
+
+    // appends the encoded input to out
+    pub fn encode_to_string<T: AsRef<[u8]>>(&self, input: T, out: &mut String) {
+        let config = &self.config;
+        let encoded = encode_with_padding(&config.alphabet, input.as_ref(), config.padding);
+        match config.wrap {
+            // a new line starts where out ends, whatever out held before
+            Some(wrap) => wrap.wrap_into(&encoded, &mut 0, out),
+            None => out.push_str(&encoded),
+        }
+    }
+
+    pub fn decode<T: AsRef<[u8]>>(&self, input: T) -> Result<Vec<u8>, DecodeError> {
+        let mut out = Vec::new();
+        self.decode_vec(input, &mut out)?;
+        Ok(out)
+    }
+
+    // appends the decoded input to out, which is left untouched if decoding fails
+    pub fn decode_vec<T: AsRef<[u8]>>(
+        &self,
+        input: T,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        let input = input.as_ref();
+        // every alphabet is made of characters, so the input has to be text.
+        // The first byte that isn't valid utf-8 can't be part of the alphabet either
+        let text = std::str::from_utf8(input).map_err(|err| DecodeError::InvalidByte {
+            offset: err.valid_up_to(),
+            byte: input[err.valid_up_to()],
+        })?;
+        let config = &self.config;
+        out.extend(decode_with_strictness(
+            &config.alphabet,
+            text,
+            config.padding,
+            config.strictness,
+        )?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode, encode, encode_wrapped, BCRYPT};
+    use super::*;
+
+    #[test]
+    fn ready_made_engines() {
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        assert_eq!(STANDARD.encode(&data), encode(&data));
+        assert_eq!(STANDARD.decode(encode(&data)).unwrap(), data);
+        assert_eq!(URL_SAFE.encode([0xfb, 0xff]), "-_8=");
+        assert_eq!(URL_SAFE.decode(b"-_8=").unwrap(), [0xfb, 0xff]);
+
+        let mime = MIME.encode(&data);
+        assert_eq!(
+            mime,
+            encode_wrapped(&Classic, &data, Padding::Pad, LineWrap::MIME)
+        );
+        assert_eq!(MIME.decode(&mime).unwrap(), data);
+    }
+
+    #[test]
+    fn configured_engine() {
+        let engine = Config::new(BCRYPT).strictness(Strictness::Strict).build();
+        assert_eq!(engine.encode(b"\xff\xff"), "996");
+        assert_eq!(engine.decode("996").unwrap(), [0xff, 0xff]);
+        assert_eq!(
+            engine.decode("997"),
+            Err(DecodeError::InvalidLastSymbol {
+                offset: 2,
+                byte: b'7'
+            })
+        );
+
+        let engine = Config::new(UrlSafe)
+            .padding(Padding::NoPad)
+            .line_wrap(LineWrap::PEM)
+            .build();
+        let encoded = engine.encode([0xfb; 100]);
+        assert!(!encoded.contains('='));
+        assert_eq!(encoded.lines().next().unwrap().len(), 64);
+        assert_eq!(engine.decode(&encoded).unwrap(), [0xfb; 100].to_vec());
+
+        let strict = Config::new(UrlSafe)
+            .padding(Padding::NoPad)
+            .strictness(Strictness::Strict)
+            .build();
+        assert_eq!(
+            strict.decode(&encoded),
+            Err(DecodeError::InvalidByte {
+                offset: 64,
+                byte: b'\n'
+            })
+        );
+        assert_eq!(
+            strict.decode(encoded.replace('\n', "")).unwrap(),
+            [0xfb; 100].to_vec()
+        );
+    }
+
+    #[test]
+    fn appends_to_buffers() {
+        let mut out = String::from("data:");
+        STANDARD.encode_to_string(b"foo", &mut out);
+        assert_eq!(out, "data:Zm9v");
+
+        let mut out = b"foo".to_vec();
+        STANDARD.decode_vec("YmFy", &mut out).unwrap();
+        assert_eq!(out, b"foobar");
+        assert!(STANDARD.decode_vec("YmF", &mut out).is_err());
+        assert_eq!(out, b"foobar");
+    }
+
+    #[test]
+    fn input_is_not_text() {
+        assert_eq!(
+            STANDARD.decode(b"Zm9v\xffA=="),
+            Err(DecodeError::InvalidByte {
+                offset: 4,
+                byte: 0xff
+            })
+        );
+        assert_eq!(decode("Zm9v"), STANDARD.decode(String::from("Zm9v")));
+    }
+}
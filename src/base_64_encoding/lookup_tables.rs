@@ -0,0 +1,469 @@
+// The fast path. Asking the Alphabet for every character, and collecting every chunk in a Vec,
+// costs far more than the bit shuffling itself. Here the alphabet is asked once, up front,
+// to fill two tables: one from 6-bit index to ascii byte and one with an entry for each of the
+// 256 possible input bytes. After that, encoding and decoding are only table lookups and shifts,
+// writing into a buffer the caller provides, so nothing is allocated.
+// Decoding accepts exactly what decode_with_padding accepts and reports the same errors.
+use super::{Alphabet, DecodeError, Padding};
+use std::fmt;
+
+// markers in the decode table, every valid entry is a 6-bit index below 64
+const INVALID: u8 = 0xff;
+const WHITESPACE: u8 = 0xfe;
+const PADDING: u8 = 0xfd;
+
+pub struct LookupTables {
+    encode: [u8; 64],
+    decode: [u8; 256],
+    padding: Option<u8>,
+}
+
+impl LookupTables {
+    // None if one of the characters of the alphabet, or its padding, is not ascii
+    pub fn new<T: Alphabet>(alphabet: &T) -> Option<LookupTables> {
+        let mut encode = [0; 64];
+        for (index, entry) in encode.iter_mut().enumerate() {
+            let character = alphabet.get_char_for_index(index as u8)?;
+            if !character.is_ascii() {
+                return None;
+            }
+            *entry = character as u8;
+        }
+
+        let padding = alphabet.get_optional_padding_char();
+        if padding.is_some_and(|padding| !padding.is_ascii()) {
+            return None;
+        }
+
+        // same order as decode_with_padding: whitespace is skipped first, then padding is set apart
+        let mut decode = [INVALID; 256];
+        for (byte, entry) in decode.iter_mut().enumerate().take(128) {
+            let character = byte as u8 as char;
+            *entry = if character.is_ascii_whitespace() {
+                WHITESPACE
+            } else if Some(character) == padding {
+                PADDING
+            } else {
+                alphabet.get_index_for_char(character).unwrap_or(INVALID)
+            };
+        }
+
+        Some(LookupTables {
+            encode,
+            decode,
+            padding: padding.map(|padding| padding as u8),
+        })
+    }
+}
+
+impl LookupTables {
+    // like Padding::for_alphabet, there is nothing to pad with if the alphabet has no padding
+    fn policy(&self, padding: Padding) -> Padding {
+        match self.padding {
+            Some(_) => padding,
+            None => Padding::NoPad,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputTooSmall;
+
+impl fmt::Display for OutputTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "output buffer too small")
+    }
+}
+
+impl std::error::Error for OutputTooSmall {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeSliceError {
+    Decode(DecodeError),
+    OutputTooSmall,
+}
+
+impl fmt::Display for DecodeSliceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeSliceError::Decode(err) => err.fmt(f),
+            DecodeSliceError::OutputTooSmall => OutputTooSmall.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DecodeSliceError {}
+
+impl From<DecodeError> for DecodeSliceError {
+    fn from(err: DecodeError) -> Self {
+        DecodeSliceError::Decode(err)
+    }
+}
+
+// Exact number of characters data_len bytes encode to, None if that doesn't fit a usize
+pub fn encoded_len(data_len: usize, padding: Padding) -> Option<usize> {
+    let whole = (data_len / 3).checked_mul(4)?;
+    let last = match (data_len % 3, padding) {
+        (0, _) => 0,
+        (remainder, Padding::NoPad) => remainder + 1,
+        _ => 4,
+    };
+    whole.checked_add(last)
+}
+
+// Upper bound for the number of bytes encoded_len characters decode to.
+// Whitespace and padding make the actual number smaller.
+pub fn decoded_len_estimate(encoded_len: usize) -> usize {
+    encoded_len.div_ceil(4) * 3
+}
+
+// Encodes data into the start of out and returns the number of bytes written
+pub fn encode_to_slice(
+    tables: &LookupTables,
+    data: &[u8],
+    out: &mut [u8],
+    padding: Padding,
+) -> Result<usize, OutputTooSmall> {
+    let padding = tables.policy(padding);
+    let len = encoded_len(data.len(), padding).ok_or(OutputTooSmall)?;
+    if out.len() < len {
+        return Err(OutputTooSmall);
+    }
+    let table = &tables.encode;
+
+    // the same split as in split(), but the 24 bits go through a single integer
+    let mut written = 0;
+    let mut chunks = data.chunks_exact(3);
+    for chunk in &mut chunks {
+        let bits = (chunk[0] as usize) << 16 | (chunk[1] as usize) << 8 | chunk[2] as usize;
+        out[written] = table[bits >> 18];
+        out[written + 1] = table[bits >> 12 & 0b111111];
+        out[written + 2] = table[bits >> 6 & 0b111111];
+        out[written + 3] = table[bits & 0b111111];
+        written += 4;
+    }
+
+    match *chunks.remainder() {
+        [a] => {
+            out[written] = table[(a >> 2) as usize];
+            out[written + 1] = table[((a & 0b00000011) << 4) as usize];
+            written += 2;
+        }
+        [a, b] => {
+            out[written] = table[(a >> 2) as usize];
+            out[written + 1] = table[((a & 0b00000011) << 4 | b >> 4) as usize];
+            out[written + 2] = table[((b & 0b00001111) << 2) as usize];
+            written += 3;
+        }
+        _ => {}
+    }
+    if let Some(padding) = tables.padding {
+        for byte in out[written..len].iter_mut() {
+            *byte = padding;
+        }
+    }
+    Ok(len)
+}
+
+// Decodes input into the start of out and returns the number of bytes written.
+// decoded_len_estimate tells how big out needs to be.
+pub fn decode_to_slice(
+    tables: &LookupTables,
+    input: &[u8],
+    out: &mut [u8],
+    padding: Padding,
+) -> Result<usize, DecodeSliceError> {
+    let padding = tables.policy(padding);
+
+    // the trailing padding, possibly broken up by whitespace
+    let mut end = input.len();
+    let mut pad_len = 0;
+    while end > 0 {
+        match tables.decode[input[end - 1] as usize] {
+            WHITESPACE => {}
+            PADDING => pad_len += 1,
+            _ => break,
+        }
+        end -= 1;
+    }
+    let input = &input[..end];
+
+    let mut decoder = SliceDecoder {
+        tables,
+        out,
+        written: 0,
+    };
+    let mut group = [0; 4];
+    let mut offsets = [0; 4];
+    let mut group_len = 0;
+    let mut symbols = 0;
+    let mut ascii = true;
+    let mut i = 0;
+
+    while i < input.len() {
+        // the fast path: nothing pending, and the next 4 bytes are all plain symbols.
+        // Every marker is above 63, so or-ing the entries only stays below 64 if all of them are symbols
+        if group_len == 0 && i + 4 <= input.len() && decoder.written + 3 <= decoder.out.len() {
+            let a = tables.decode[input[i] as usize];
+            let b = tables.decode[input[i + 1] as usize];
+            let c = tables.decode[input[i + 2] as usize];
+            let d = tables.decode[input[i + 3] as usize];
+            if a | b | c | d < 64 {
+                let out = &mut decoder.out[decoder.written..decoder.written + 3];
+                out[0] = a << 2 | b >> 4;
+                out[1] = b << 4 | c >> 2;
+                out[2] = c << 6 | d;
+                decoder.written += 3;
+                symbols += 4;
+                i += 4;
+                continue;
+            }
+        }
+
+        // the slow path takes one byte at a time, remembering offsets for the errors
+        if tables.decode[input[i] as usize] != WHITESPACE {
+            ascii &= input[i].is_ascii();
+            group[group_len] = input[i];
+            offsets[group_len] = i;
+            group_len += 1;
+            symbols += 1;
+        }
+        i += 1;
+
+        if group_len == 4 {
+            if let Err(err) = decoder.group(&group, &offsets) {
+                return Err(decoder.precedence(err, input, pad_len, padding));
+            }
+            group_len = 0;
+        }
+    }
+
+    // the left over characters must form a quantum that follows the padding policy.
+    // Symbols counts bytes, which only matches the characters if they were all ascii
+    if !ascii {
+        symbols = count_chars(tables, input);
+    }
+    padding.check(symbols, pad_len)?;
+    if group_len > 0 {
+        decoder.group(&group[..group_len], &offsets[..group_len])?;
+    }
+    Ok(decoder.written)
+}
+
+struct SliceDecoder<'a> {
+    tables: &'a LookupTables,
+    out: &'a mut [u8],
+    written: usize,
+}
+
+impl<'a> SliceDecoder<'a> {
+    // a group off the fast path, it's handled the way original() and stitch() do
+    fn group(&mut self, group: &[u8], offsets: &[usize]) -> Result<(), DecodeSliceError> {
+        let mut indices = [0; 4];
+        let mut len = 0;
+        for (&byte, &offset) in group.iter().zip(offsets) {
+            match self.tables.decode[byte as usize] {
+                PADDING => {}
+                INVALID => return Err(DecodeError::InvalidByte { offset, byte }.into()),
+                index => {
+                    indices[len] = index;
+                    len += 1;
+                }
+            }
+        }
+        if len < 2 {
+            return Err(DecodeError::InvalidPadding.into());
+        }
+
+        let bytes = [
+            indices[0] << 2 | indices[1] >> 4,
+            indices[1] << 4 | indices[2] >> 2,
+            indices[2] << 6 | indices[3],
+        ];
+        let out = self
+            .out
+            .get_mut(self.written..self.written + len - 1)
+            .ok_or(DecodeSliceError::OutputTooSmall)?;
+        out.copy_from_slice(&bytes[..len - 1]);
+        self.written += len - 1;
+        Ok(())
+    }
+
+    // decode_with_padding checks the length and padding before looking at any character,
+    // so if those are wrong as well, that's the error to report
+    fn precedence(
+        &self,
+        err: DecodeSliceError,
+        input: &[u8],
+        pad_len: usize,
+        padding: Padding,
+    ) -> DecodeSliceError {
+        match padding.check(count_chars(self.tables, input), pad_len) {
+            Err(check) => check.into(),
+            Ok(()) => err,
+        }
+    }
+}
+
+// The number of characters that aren't whitespace, the way decode_with_padding counts them:
+// a character outside of ascii is a single character, however many bytes it takes.
+fn count_chars(tables: &LookupTables, input: &[u8]) -> usize {
+    input
+        .iter()
+        .filter(|&&byte| {
+            byte & 0b11000000 != 0b10000000 && tables.decode[byte as usize] != WHITESPACE
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode_with_padding, encode_with_padding, Classic, UrlSafe};
+    use super::*;
+    use crate::test_util::Lcg;
+
+    const PADDINGS: [Padding; 3] = [Padding::Pad, Padding::NoPad, Padding::Indifferent];
+
+    fn decode_vec(
+        tables: &LookupTables,
+        input: &str,
+        padding: Padding,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let mut out = vec![0; decoded_len_estimate(input.len())];
+        match decode_to_slice(tables, input.as_bytes(), &mut out, padding) {
+            Ok(len) => Ok(out[..len].to_vec()),
+            Err(DecodeSliceError::Decode(err)) => Err(err),
+            Err(DecodeSliceError::OutputTooSmall) => panic!("estimate too small for {:?}", input),
+        }
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(encoded_len(0, Padding::Pad), Some(0));
+        assert_eq!(encoded_len(1, Padding::Pad), Some(4));
+        assert_eq!(encoded_len(1, Padding::NoPad), Some(2));
+        assert_eq!(encoded_len(5, Padding::NoPad), Some(7));
+        assert_eq!(encoded_len(6, Padding::Indifferent), Some(8));
+        assert_eq!(encoded_len(usize::MAX, Padding::Pad), None);
+
+        assert_eq!(decoded_len_estimate(0), 0);
+        assert_eq!(decoded_len_estimate(2), 3);
+        assert_eq!(decoded_len_estimate(8), 6);
+    }
+
+    #[test]
+    fn encode_matches_split_path() {
+        let classic = LookupTables::new(&Classic).unwrap();
+        let url_safe = LookupTables::new(&UrlSafe).unwrap();
+        let data = (0..=255_u8).rev().collect::<Vec<u8>>();
+        for len in 0..=64 {
+            for padding in PADDINGS.iter() {
+                let mut out = [0; 100];
+                let written = encode_to_slice(&classic, &data[..len], &mut out, *padding).unwrap();
+                assert_eq!(
+                    out[..written],
+                    *encode_with_padding(&Classic, &data[..len], *padding).as_bytes()
+                );
+                let written = encode_to_slice(&url_safe, &data[..len], &mut out, *padding).unwrap();
+                assert_eq!(
+                    out[..written],
+                    *encode_with_padding(&UrlSafe, &data[..len], *padding).as_bytes()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decode_matches_split_path() {
+        let tables = LookupTables::new(&Classic).unwrap();
+        let mut lcg = Lcg::new(12345);
+        let mut random = move |below: usize| lcg.below(below);
+        let noise = ['=', ' ', '\n', '!', '-', 'A', '\u{e9}'];
+
+        for len in 0..=40 {
+            let data = (0..len).map(|_| random(256) as u8).collect::<Vec<u8>>();
+            for padding in PADDINGS.iter() {
+                let encoded = encode_with_padding(&Classic, &data, *padding);
+                assert_eq!(decode_vec(&tables, &encoded, *padding).unwrap(), data);
+
+                // insert, replace or remove characters and make sure both paths still agree
+                for _ in 0..20 {
+                    let mut chars = encoded.chars().collect::<Vec<char>>();
+                    for _ in 0..=random(3) {
+                        let position = random(chars.len() + 1);
+                        match random(3) {
+                            0 => chars.insert(position, noise[random(noise.len())]),
+                            1 if position < chars.len() => {
+                                chars[position] = noise[random(noise.len())]
+                            }
+                            _ if position < chars.len() => {
+                                chars.remove(position);
+                            }
+                            _ => {}
+                        }
+                    }
+                    let corrupted = chars.into_iter().collect::<String>();
+                    for policy in PADDINGS.iter() {
+                        assert_eq!(
+                            decode_vec(&tables, &corrupted, *policy),
+                            decode_with_padding(Classic, &corrupted, *policy),
+                            "{:?} {:?}",
+                            corrupted,
+                            policy
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn output_too_small() {
+        let tables = LookupTables::new(&Classic).unwrap();
+        let mut out = [0; 7];
+        assert_eq!(
+            encode_to_slice(&tables, b"foobar", &mut out, Padding::Pad),
+            Err(OutputTooSmall)
+        );
+        assert_eq!(
+            encode_to_slice(&tables, b"fooba", &mut out, Padding::NoPad),
+            Ok(7)
+        );
+
+        let mut out = [0; 5];
+        assert_eq!(
+            decode_to_slice(&tables, b"Zm9vYmFy", &mut out, Padding::Pad),
+            Err(DecodeSliceError::OutputTooSmall)
+        );
+        assert_eq!(
+            decode_to_slice(&tables, b"Zm9vYmE=", &mut out, Padding::Pad),
+            Ok(5)
+        );
+        assert_eq!(&out, b"fooba");
+    }
+
+    struct NotAscii;
+
+    impl Alphabet for NotAscii {
+        fn get_char_for_index(&self, index: u8) -> Option<char> {
+            Classic.get_char_for_index(index).map(|character| {
+                if character == '/' {
+                    '\u{b7}'
+                } else {
+                    character
+                }
+            })
+        }
+        fn get_index_for_char(&self, character: char) -> Option<u8> {
+            Classic.get_index_for_char(character)
+        }
+        fn get_padding_char(&self) -> char {
+            '='
+        }
+    }
+
+    #[test]
+    fn tables_need_ascii() {
+        assert!(LookupTables::new(&NotAscii).is_none());
+    }
+}
@@ -0,0 +1,248 @@
+// Encoding and decoding big buffers on several threads. Every 3 bytes encode to 4 characters on their own,
+// so the input is cut into one piece per thread at a quantum boundary, and each piece is written
+// straight into its own part of an output allocated once, up front. The pieces go through the lookup tables.
+//
+// A decoded piece only lines up with its part of the output if it is nothing but symbols, which isn't known
+// before decoding it. So if a piece other than the last one comes up short or fails (whitespace, padding in
+// the middle, an invalid byte), the whole input is decoded again on one thread: the result, or the error
+// with its offset, is then exactly the one decode_to_slice gives.
+//
+// Starting threads costs more than encoding a small buffer, below the threshold one thread does all the work.
+use super::lookup_tables::{
+    decode_to_slice, decoded_len_estimate, encode_to_slice, encoded_len, DecodeSliceError,
+    LookupTables,
+};
+use super::{decode_with_padding, encode_with_padding, Alphabet, DecodeError, Padding};
+use std::thread;
+
+// in input bytes
+const DEFAULT_THRESHOLD: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parallel {
+    // None is one thread per cpu
+    threads: Option<usize>,
+    threshold: usize,
+}
+
+impl Parallel {
+    pub const fn new() -> Self {
+        Parallel {
+            threads: None,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    // 0 is taken as 1
+    pub const fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(if threads == 0 { 1 } else { threads });
+        self
+    }
+
+    // inputs shorter than this many bytes are done on the calling thread
+    pub const fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    // how many threads input_len bytes get, 1 meaning the calling thread alone
+    fn threads_for(&self, input_len: usize) -> usize {
+        if input_len == 0 || input_len < self.threshold {
+            return 1;
+        }
+        self.threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()))
+    }
+}
+
+impl Default for Parallel {
+    fn default() -> Self {
+        Parallel::new()
+    }
+}
+
+// bytes per piece so that threads pieces cover len, a multiple of quantum
+fn piece_len(len: usize, threads: usize, quantum: usize) -> usize {
+    len.div_ceil(threads).div_ceil(quantum).max(1) * quantum
+}
+
+pub fn encode_parallel<T: Alphabet>(
+    alphabet: &T,
+    data: &[u8],
+    padding: Padding,
+    parallel: &Parallel,
+) -> String {
+    let tables = match LookupTables::new(alphabet) {
+        Some(tables) => tables,
+        None => return encode_with_padding(alphabet, data, padding),
+    };
+    let len = encoded_len(data.len(), padding).expect("encoded length overflows usize");
+    let mut out = vec![0; len];
+
+    let threads = parallel.threads_for(data.len());
+    if threads == 1 {
+        encode_to_slice(&tables, data, &mut out, padding).expect("output has the encoded length");
+    } else {
+        let piece = piece_len(data.len(), threads, 3);
+        thread::scope(|scope| {
+            // only the last piece can end in a partial quantum, so only its part of the output is padded
+            for (input, output) in data.chunks(piece).zip(out.chunks_mut(piece / 3 * 4)) {
+                let tables = &tables;
+                scope.spawn(move || {
+                    encode_to_slice(tables, input, output, padding)
+                        .expect("output has the encoded length")
+                });
+            }
+        });
+    }
+    String::from_utf8(out).expect("lookup tables only hold ascii")
+}
+
+pub fn decode_parallel<T: Alphabet, D: AsRef<[u8]>>(
+    alphabet: &T,
+    input: D,
+    padding: Padding,
+    parallel: &Parallel,
+) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_ref();
+    let tables = match LookupTables::new(alphabet) {
+        Some(tables) => tables,
+        None => return decode_without_tables(alphabet, input, padding),
+    };
+    let mut out = vec![0; decoded_len_estimate(input.len())];
+
+    let threads = parallel.threads_for(input.len());
+    let written = if threads == 1 {
+        decode_whole(&tables, input, &mut out, padding)?
+    } else {
+        let piece = piece_len(input.len(), threads, 4);
+        let pieces = input.len().div_ceil(piece);
+        let results = thread::scope(|scope| {
+            let handles = input
+                .chunks(piece)
+                .zip(out.chunks_mut(piece / 4 * 3))
+                .enumerate()
+                .map(|(index, (input, output))| {
+                    let tables = &tables;
+                    scope.spawn(move || {
+                        // the padding can only be at the end of the last piece
+                        let last = index == pieces - 1;
+                        let policy = if last { padding } else { Padding::NoPad };
+                        let written = decode_to_slice(tables, input, output, policy).ok()?;
+                        if last || written == output.len() {
+                            Some(written)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("decoding thread panicked"))
+                .collect::<Option<Vec<usize>>>()
+        });
+
+        match results {
+            // every piece but the last filled its part
+            Some(results) => (pieces - 1) * (piece / 4 * 3) + results[pieces - 1],
+            None => decode_whole(&tables, input, &mut out, padding)?,
+        }
+    };
+    out.truncate(written);
+    Ok(out)
+}
+
+fn decode_whole(
+    tables: &LookupTables,
+    input: &[u8],
+    out: &mut [u8],
+    padding: Padding,
+) -> Result<usize, DecodeError> {
+    decode_to_slice(tables, input, out, padding).map_err(|err| match err {
+        DecodeSliceError::Decode(err) => err,
+        DecodeSliceError::OutputTooSmall => unreachable!("output has the estimated length"),
+    })
+}
+
+// an alphabet the tables can't hold goes through decode_with_padding, which needs text
+fn decode_without_tables<T: Alphabet>(
+    alphabet: &T,
+    input: &[u8],
+    padding: Padding,
+) -> Result<Vec<u8>, DecodeError> {
+    let text = std::str::from_utf8(input).map_err(|err| DecodeError::InvalidByte {
+        offset: err.valid_up_to(),
+        byte: input[err.valid_up_to()],
+    })?;
+    decode_with_padding(alphabet, text, padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Classic, UrlSafe};
+    use super::*;
+
+    // every input goes through the threads, with more threads than some inputs have quanta
+    const EVERY_SIZE: Parallel = Parallel::new().threads(4).threshold(0);
+
+    #[test]
+    fn matches_single_threaded_codec() {
+        let data = (0..=255_u8).cycle().take(1000).collect::<Vec<u8>>();
+        for len in (0..40).chain(995..1000) {
+            let data = &data[..len];
+            for &padding in [Padding::Pad, Padding::NoPad].iter() {
+                for threads in 1..6 {
+                    let parallel = Parallel::new().threads(threads).threshold(0);
+                    let encoded = encode_parallel(&UrlSafe, data, padding, &parallel);
+                    assert_eq!(encoded, encode_with_padding(&UrlSafe, data, padding));
+                    assert_eq!(
+                        decode_parallel(&UrlSafe, &encoded, padding, &parallel).unwrap(),
+                        data
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn large_buffer_with_default_threads() {
+        let data = (0..3 << 20)
+            .map(|i: u32| ((i * 7) >> 3) as u8)
+            .collect::<Vec<u8>>();
+        let encoded = encode_parallel(&Classic, &data, Padding::Pad, &Parallel::new());
+        assert_eq!(encoded, encode_with_padding(&Classic, &data, Padding::Pad));
+        assert_eq!(
+            decode_parallel(&Classic, &encoded, Padding::Pad, &Parallel::new()).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn irregular_input_falls_back() {
+        // whitespace and padding in the middle move the quantum boundaries, the result has to be the same
+        let inputs = [
+            "Zm9v\nYmFy\r\nYmF6IGJh\ncg==",
+            "Zm9vYmE=Zm9vYmFyYmF6",
+            " Zm9vYmFyYmF6YmF6YmF6YmF6",
+            "Zm9vYmFyYmF*YmF6YmF6YmF6",
+            "Zm9vYmFyYmF6YmF6YmF6YmF6Y",
+            "Zm9vYmFyYmF6YmF6YmF6Ym==",
+        ];
+        for input in inputs.iter() {
+            assert_eq!(
+                decode_parallel(&Classic, input, Padding::Indifferent, &EVERY_SIZE),
+                decode_with_padding(Classic, input, Padding::Indifferent),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            decode_parallel(&Classic, b"Zm9v\xffmFy", Padding::Pad, &EVERY_SIZE),
+            Err(DecodeError::InvalidByte {
+                offset: 4,
+                byte: 0xff
+            })
+        );
+    }
+}
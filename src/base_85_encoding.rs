@@ -0,0 +1,290 @@
+// Base85 packs 4 bytes into 5 characters: 85^5 is just above 2^32, so a group of 4 bytes read as a
+// big-endian u32 can always be written as 5 base 85 digits. That's 25% overhead against 33% for Base64.
+// Two variants are used:
+//
+// Adobe's Ascii85 (PostScript and PDF) maps the digits to the characters '!' (33) to 'u' (117).
+// A group of 4 zero bytes is shortened to a single 'z', and the output is framed by "<~" and "~>".
+// A last group of n < 4 bytes is filled up with zeroes and only its first n + 1 characters are written,
+// the decoder fills those back up with 'u' (the highest digit) and drops the same number of bytes.
+// Whitespace is ignored when decoding.
+//
+// ZeroMQ's Z85 (RFC 32) has an alphabet that is safe in source code and has no shortcuts or partial
+// groups: the input must be a multiple of 4 bytes, and the encoded text a multiple of 5 characters.
+use crate::base_64_encoding::DecodeError;
+use std::convert::TryFrom;
+use std::fmt;
+
+const ASCII85_OFFSET: u8 = b'!';
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base85Error {
+    // a byte outside of the alphabet, offset is the byte position in the input
+    Decode(DecodeError),
+    // Z85 input that is not a multiple of 4 bytes or 5 characters, or a last Ascii85 group of 1 character
+    InvalidLength,
+    // a group of 5 characters is worth more than a u32, offset is its first character
+    Overflow { offset: usize },
+}
+
+impl fmt::Display for Base85Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Base85Error::Decode(err) => err.fmt(f),
+            Base85Error::InvalidLength => write!(f, "invalid input length"),
+            Base85Error::Overflow { offset } => {
+                write!(f, "group at offset {} overflows 32 bits", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Base85Error {}
+
+impl From<DecodeError> for Base85Error {
+    fn from(err: DecodeError) -> Self {
+        Base85Error::Decode(err)
+    }
+}
+
+// the 5 base 85 digits of a group, most significant first
+fn split(chunk: &[u8]) -> [u8; 5] {
+    let mut group = [0; 4];
+    group[..chunk.len()].copy_from_slice(chunk);
+    let mut value = u32::from_be_bytes(group);
+
+    let mut digits = [0; 5];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+// the reverse of split, None if the digits are worth more than a u32
+fn stitch(digits: &[u8; 5]) -> Option<[u8; 4]> {
+    let value = digits
+        .iter()
+        .fold(0_u64, |value, &digit| value * 85 + digit as u64);
+    u32::try_from(value).ok().map(u32::to_be_bytes)
+}
+
+pub fn encode_ascii85(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 5 / 4 + 9);
+    out.push_str("<~");
+    for chunk in data.chunks(4) {
+        if chunk == [0, 0, 0, 0] {
+            out.push('z');
+            continue;
+        }
+        let digits = split(chunk);
+        out.extend(
+            digits[..chunk.len() + 1]
+                .iter()
+                .map(|&digit| (digit + ASCII85_OFFSET) as char),
+        );
+    }
+    out.push_str("~>");
+    out
+}
+
+// The "<~" and "~>" delimiters are optional, so are the bare strings of tools that leave them out
+pub fn decode_ascii85(data: &str) -> Result<Vec<u8>, Base85Error> {
+    let bytes = data.as_bytes();
+    let start = if data.trim_start().starts_with("<~") {
+        data.find("<~").unwrap_or(0) + 2
+    } else {
+        0
+    };
+    let end = if data.trim_end().ends_with("~>") {
+        data.rfind("~>").unwrap_or(bytes.len())
+    } else {
+        bytes.len()
+    };
+
+    let mut out = Vec::with_capacity((end.saturating_sub(start)) * 4 / 5);
+    let mut digits = [0; 5];
+    let mut group_len = 0;
+    let mut group_offset = 0;
+
+    for (offset, &byte) in bytes.iter().enumerate().take(end).skip(start) {
+        match byte {
+            _ if byte.is_ascii_whitespace() => continue,
+            // the shortcut for 4 zero bytes, it can't be part of a group
+            b'z' if group_len == 0 => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                if group_len == 0 {
+                    group_offset = offset;
+                }
+                digits[group_len] = byte - ASCII85_OFFSET;
+                group_len += 1;
+                if group_len == 5 {
+                    let group = stitch(&digits).ok_or(Base85Error::Overflow {
+                        offset: group_offset,
+                    })?;
+                    out.extend_from_slice(&group);
+                    group_len = 0;
+                }
+            }
+            _ => {
+                return Err(Base85Error::Decode(DecodeError::InvalidByte {
+                    offset,
+                    byte,
+                }))
+            }
+        }
+    }
+
+    // a partial group is filled up with the highest digit, a single character can't even hold a byte
+    match group_len {
+        0 => {}
+        1 => return Err(Base85Error::InvalidLength),
+        _ => {
+            for digit in digits[group_len..].iter_mut() {
+                *digit = 84;
+            }
+            let group = stitch(&digits).ok_or(Base85Error::Overflow {
+                offset: group_offset,
+            })?;
+            out.extend_from_slice(&group[..group_len - 1]);
+        }
+    }
+    Ok(out)
+}
+
+pub fn encode_z85(data: &[u8]) -> Result<String, Base85Error> {
+    if !data.len().is_multiple_of(4) {
+        return Err(Base85Error::InvalidLength);
+    }
+    let encoded = data
+        .chunks(4)
+        .flat_map(|chunk| split(chunk).to_vec())
+        .map(|digit| Z85_ALPHABET[digit as usize] as char)
+        .collect();
+    Ok(encoded)
+}
+
+pub fn decode_z85(data: &str) -> Result<Vec<u8>, Base85Error> {
+    let bytes = data.as_bytes();
+    if !bytes.len().is_multiple_of(5) {
+        return Err(Base85Error::InvalidLength);
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() / 5 * 4);
+    for (group, chunk) in bytes.chunks(5).enumerate() {
+        let mut digits = [0; 5];
+        for (i, &byte) in chunk.iter().enumerate() {
+            let offset = group * 5 + i;
+            digits[i] = Z85_ALPHABET
+                .iter()
+                .position(|&symbol| symbol == byte)
+                .ok_or(DecodeError::InvalidByte { offset, byte })? as u8;
+        }
+        let decoded = stitch(&digits).ok_or(Base85Error::Overflow { offset: group * 5 })?;
+        out.extend_from_slice(&decoded);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii85_vectors() {
+        let vectors: [(&[u8], &str); 6] = [
+            (b"", "<~~>"),
+            (b"Man is distinguished", "<~9jqo^BlbD-BleB1DJ+*+F(f,q~>"),
+            (b"sure.", "<~F*2M7/c~>"),
+            (b"\0\0\0\0abc\0\0\0\0", "<~z@:E^H!!!!~>"),
+            (b"\xff\xff\xff\xff", "<~s8W-!~>"),
+            (b"\0", "<~!!~>"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(encode_ascii85(plain), *encoded);
+            assert_eq!(decode_ascii85(encoded).unwrap(), *plain);
+        }
+    }
+
+    #[test]
+    fn ascii85_round_trip() {
+        let data = (0..=255_u8).rev().collect::<Vec<u8>>();
+        for len in 0..=20 {
+            assert_eq!(
+                decode_ascii85(&encode_ascii85(&data[..len])).unwrap(),
+                &data[..len]
+            );
+        }
+    }
+
+    #[test]
+    fn ascii85_decoding() {
+        // no delimiters, and whitespace anywhere
+        assert_eq!(decode_ascii85("9jqo^").unwrap(), b"Man ");
+        assert_eq!(
+            decode_ascii85("  <~9jq\no^ F*2M7/c~>\n").unwrap(),
+            b"Man sure."
+        );
+
+        assert_eq!(
+            decode_ascii85("<~9jqo^v~>"),
+            Err(Base85Error::Decode(DecodeError::InvalidByte {
+                offset: 7,
+                byte: b'v'
+            }))
+        );
+        assert_eq!(
+            decode_ascii85("<~9jzo^~>"),
+            Err(Base85Error::Decode(DecodeError::InvalidByte {
+                offset: 4,
+                byte: b'z'
+            }))
+        );
+        assert_eq!(
+            decode_ascii85("<~9jqo^F~>"),
+            Err(Base85Error::InvalidLength)
+        );
+        assert_eq!(
+            decode_ascii85("<~9jqo^s8W-\"~>"),
+            Err(Base85Error::Overflow { offset: 7 })
+        );
+        assert_eq!(
+            decode_ascii85("<~~~>"),
+            Err(Base85Error::Decode(DecodeError::InvalidByte {
+                offset: 2,
+                byte: b'~'
+            }))
+        );
+    }
+
+    #[test]
+    fn z85_vectors() {
+        // the example of RFC 32
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(encode_z85(&data).unwrap(), "HelloWorld");
+        assert_eq!(decode_z85("HelloWorld").unwrap(), data);
+
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        assert_eq!(decode_z85(&encode_z85(&data).unwrap()).unwrap(), data);
+        assert_eq!(encode_z85(b"").unwrap(), "");
+    }
+
+    #[test]
+    fn z85_errors() {
+        assert_eq!(encode_z85(b"abc"), Err(Base85Error::InvalidLength));
+        assert_eq!(decode_z85("Hell"), Err(Base85Error::InvalidLength));
+        assert_eq!(decode_z85("Hello World"), Err(Base85Error::InvalidLength));
+        assert_eq!(
+            decode_z85("HelloWor~d"),
+            Err(Base85Error::Decode(DecodeError::InvalidByte {
+                offset: 8,
+                byte: b'~'
+            }))
+        );
+        assert_eq!(
+            decode_z85("#####"),
+            Err(Base85Error::Overflow { offset: 0 })
+        );
+    }
+}
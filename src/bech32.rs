@@ -0,0 +1,541 @@
+// Bech32 (BIP-173) and Bech32m (BIP-350) are the formats of segwit addresses like
+// bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4. They are made of three parts:
+// a human-readable part (hrp) saying what the string is for, the separator '1',
+// and the data written with 32 characters, 5 bits each, followed by a 6 character checksum.
+//
+// The data is regrouped from 8-bit bytes to 5-bit groups the way split() does it for Base64's 6 bits,
+// only on a running bit buffer instead of fixed blocks.
+// The checksum is a BCH code over the hrp and the data. It detects any error in up to 4 characters,
+// and a single wrong character can be located, which the checksum error reports.
+// Bech32 and Bech32m only differ in the constant the checksum is xor-ed with,
+// Bech32m fixes a weakness of Bech32 with inserted or deleted 'q's right before a final 'p'.
+//
+// Strings are at most 90 characters and either all lowercase or all uppercase, mixing is an error.
+use crate::base_64_encoding::DecodeError;
+use std::fmt;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const SEPARATOR: char = '1';
+const CHECKSUM_LEN: usize = 6;
+const MAX_LEN: usize = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(&self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+// Offsets are positions in the string, which can only be ascii by the time they are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bech32Error {
+    // the first character whose case differs from the characters before it
+    MixedCase { offset: usize },
+    // InvalidByte for a character outside of the hrp range (33 to 126), or a data character outside the charset
+    Decode(DecodeError),
+    MissingSeparator,
+    EmptyHrp,
+    // longer than 90 characters
+    TooLong { len: usize },
+    // less than 6 characters after the separator
+    TooShort,
+    // error_offset is where a single wrong character was found, None if there is more than one
+    InvalidChecksum { error_offset: Option<usize> },
+    // regrouping bits left non-zero padding, or more padding than a group
+    InvalidPadding,
+    // a 5-bit value above 31 was given to encode
+    InvalidData,
+    // segwit only: the witness version, program length or variant don't go together
+    InvalidWitness,
+    // segwit only: the address is for another network
+    WrongHrp,
+}
+
+impl fmt::Display for Bech32Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bech32Error::MixedCase { offset } => write!(f, "mixed case at offset {}", offset),
+            Bech32Error::Decode(err) => err.fmt(f),
+            Bech32Error::MissingSeparator => write!(f, "missing separator '1'"),
+            Bech32Error::EmptyHrp => write!(f, "empty human-readable part"),
+            Bech32Error::TooLong { len } => {
+                write!(f, "{} characters, at most {} allowed", len, MAX_LEN)
+            }
+            Bech32Error::TooShort => write!(f, "data part shorter than the checksum"),
+            Bech32Error::InvalidChecksum {
+                error_offset: Some(offset),
+            } => {
+                write!(f, "invalid checksum, probable error at offset {}", offset)
+            }
+            Bech32Error::InvalidChecksum { error_offset: None } => write!(f, "invalid checksum"),
+            Bech32Error::InvalidPadding => write!(f, "invalid padding"),
+            Bech32Error::InvalidData => write!(f, "data value doesn't fit in 5 bits"),
+            Bech32Error::InvalidWitness => write!(f, "invalid witness version or program"),
+            Bech32Error::WrongHrp => write!(f, "unexpected human-readable part"),
+        }
+    }
+}
+
+impl std::error::Error for Bech32Error {}
+
+impl From<DecodeError> for Bech32Error {
+    fn from(err: DecodeError) -> Self {
+        Bech32Error::Decode(err)
+    }
+}
+
+// The BCH checksum: the values are the coefficients of a polynomial over GF(32),
+// and this is its remainder modulo the generator, kept in 30 bits
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum = 1_u32;
+    for &value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x1ffffff) << 5 ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if top >> i & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+// the hrp goes into the checksum as the high 3 bits of each character, a 0, then the low 5 bits
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let bytes = hrp.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len() * 2 + 1);
+    out.extend(bytes.iter().map(|byte| byte >> 5));
+    out.push(0);
+    out.extend(bytes.iter().map(|byte| byte & 0b11111));
+    out
+}
+
+fn checksum_input(hrp: &str, data: &[u8]) -> Vec<u8> {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values
+}
+
+fn create_checksum(hrp: &str, data: &[u8], variant: Variant) -> [u8; CHECKSUM_LEN] {
+    let mut values = checksum_input(hrp, data);
+    values.extend_from_slice(&[0; CHECKSUM_LEN]);
+    let checksum = polymod(&values) ^ variant.constant();
+
+    let mut out = [0; CHECKSUM_LEN];
+    for (i, value) in out.iter_mut().enumerate() {
+        *value = (checksum >> (5 * (5 - i)) & 0b11111) as u8;
+    }
+    out
+}
+
+// Regroups a stream of from-bit values into to-bit values, most significant bit first.
+// With pad the last group is filled up with zeroes, without it the left over bits must be
+// fewer than from and all zero, the way they are when they are the padding of the other direction.
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, Bech32Error> {
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    let max = (1 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+
+    for &value in data {
+        if (value as u32) >> from != 0 {
+            return Err(Bech32Error::InvalidData);
+        }
+        buffer = buffer << from | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push((buffer >> bits & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            out.push((buffer << (to - bits) & max) as u8);
+        }
+    } else if bits >= from || buffer << (to - bits) & max != 0 {
+        return Err(Bech32Error::InvalidPadding);
+    }
+    Ok(out)
+}
+
+// data holds 5-bit values, the output is lowercase
+pub fn encode_u5(hrp: &str, data: &[u8], variant: Variant) -> Result<String, Bech32Error> {
+    check_hrp(hrp)?;
+    if hrp.bytes().any(|byte| byte.is_ascii_uppercase())
+        && hrp.bytes().any(|byte| byte.is_ascii_lowercase())
+    {
+        return Err(Bech32Error::MixedCase {
+            offset: first_case_change(hrp).unwrap_or(0),
+        });
+    }
+    if data.iter().any(|&value| value > 31) {
+        return Err(Bech32Error::InvalidData);
+    }
+    let len = hrp.len() + 1 + data.len() + CHECKSUM_LEN;
+    if len > MAX_LEN {
+        return Err(Bech32Error::TooLong { len });
+    }
+
+    // the checksum is computed over the lowercase hrp, the one that is written
+    let hrp = hrp.to_ascii_lowercase();
+    let checksum = create_checksum(&hrp, data, variant);
+    let mut out = String::with_capacity(len);
+    out.push_str(&hrp);
+    out.push(SEPARATOR);
+    out.extend(
+        data.iter()
+            .chain(checksum.iter())
+            .map(|&value| CHARSET[value as usize] as char),
+    );
+    Ok(out)
+}
+
+// data holds bytes, they are regrouped into 5-bit values
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String, Bech32Error> {
+    encode_u5(hrp, &convert_bits(data, 8, 5, true)?, variant)
+}
+
+// Returns the lowercase hrp, the 5-bit data values without the checksum, and the variant the checksum matched
+pub fn decode_u5(encoded: &str) -> Result<(String, Vec<u8>, Variant), Bech32Error> {
+    let bytes = encoded.as_bytes();
+    if let Some(offset) = encoded.bytes().position(|byte| !(33..=126).contains(&byte)) {
+        return Err(Bech32Error::Decode(DecodeError::InvalidByte {
+            offset,
+            byte: bytes[offset],
+        }));
+    }
+    if bytes.len() > MAX_LEN {
+        return Err(Bech32Error::TooLong { len: bytes.len() });
+    }
+    if let Some(offset) = first_case_change(encoded) {
+        return Err(Bech32Error::MixedCase { offset });
+    }
+    let lowercase = encoded.to_ascii_lowercase();
+
+    // the hrp can contain '1' as well, the separator is the last one
+    let separator = lowercase
+        .rfind(SEPARATOR)
+        .ok_or(Bech32Error::MissingSeparator)?;
+    if separator == 0 {
+        return Err(Bech32Error::EmptyHrp);
+    }
+    if bytes.len() - separator - 1 < CHECKSUM_LEN {
+        return Err(Bech32Error::TooShort);
+    }
+    let hrp = &lowercase[..separator];
+
+    let mut data = Vec::with_capacity(bytes.len() - separator - 1);
+    for (offset, byte) in lowercase.bytes().enumerate().skip(separator + 1) {
+        let value =
+            CHARSET
+                .iter()
+                .position(|&symbol| symbol == byte)
+                .ok_or(DecodeError::InvalidByte {
+                    offset,
+                    byte: bytes[offset],
+                })?;
+        data.push(value as u8);
+    }
+
+    let residue = polymod(&checksum_input(hrp, &data));
+    let variant = match residue {
+        residue if residue == Variant::Bech32.constant() => Variant::Bech32,
+        residue if residue == Variant::Bech32m.constant() => Variant::Bech32m,
+        _ => {
+            let error_offset = locate_error(hrp, &data).map(|index| separator + 1 + index);
+            return Err(Bech32Error::InvalidChecksum { error_offset });
+        }
+    };
+
+    data.truncate(data.len() - CHECKSUM_LEN);
+    Ok((hrp.to_string(), data, variant))
+}
+
+// Returns the lowercase hrp, the data regrouped into bytes, and the variant
+pub fn decode(encoded: &str) -> Result<(String, Vec<u8>, Variant), Bech32Error> {
+    let (hrp, data, variant) = decode_u5(encoded)?;
+    Ok((hrp, convert_bits(&data, 5, 8, false)?, variant))
+}
+
+fn check_hrp(hrp: &str) -> Result<(), Bech32Error> {
+    if hrp.is_empty() {
+        return Err(Bech32Error::EmptyHrp);
+    }
+    match hrp.bytes().position(|byte| !(33..=126).contains(&byte)) {
+        Some(offset) => Err(Bech32Error::Decode(DecodeError::InvalidByte {
+            offset,
+            byte: hrp.as_bytes()[offset],
+        })),
+        None => Ok(()),
+    }
+}
+
+// the offset of the first letter whose case is not the case of the first letter
+fn first_case_change(text: &str) -> Option<usize> {
+    let mut letters = text
+        .bytes()
+        .enumerate()
+        .filter(|(_, byte)| byte.is_ascii_alphabetic());
+    let (_, first) = letters.next()?;
+    letters
+        .find(|(_, byte)| byte.is_ascii_uppercase() != first.is_ascii_uppercase())
+        .map(|(offset, _)| offset)
+}
+
+// The code corrects a single error, so if exactly one character can be changed into another
+// that makes the checksum valid (for either variant), that's where the error is.
+// Trying each of them is cheap enough for strings of 90 characters at most.
+fn locate_error(hrp: &str, data: &[u8]) -> Option<usize> {
+    let mut values = checksum_input(hrp, data);
+    let start = values.len() - data.len();
+    let mut found = None;
+
+    for index in 0..data.len() {
+        let original = values[start + index];
+        for candidate in (0..32).filter(|&candidate| candidate != original) {
+            values[start + index] = candidate;
+            let residue = polymod(&values);
+            if residue == Variant::Bech32.constant() || residue == Variant::Bech32m.constant() {
+                // two different fixes means we can't tell which one it is
+                if found.is_some() && found != Some(index) {
+                    return None;
+                }
+                found = Some(index);
+            }
+        }
+        values[start + index] = original;
+    }
+    found
+}
+
+// A segwit address holds a witness version (0 to 16) as its first 5-bit value, then the witness program.
+// Version 0 uses Bech32 with a program of 20 or 32 bytes, later versions use Bech32m with 2 to 40 bytes.
+pub fn encode_segwit_address(
+    hrp: &str,
+    version: u8,
+    program: &[u8],
+) -> Result<String, Bech32Error> {
+    let variant = check_witness(version, program)?;
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true)?);
+    encode_u5(hrp, &data, variant)
+}
+
+// Returns the witness version and program, the address has to be for the given hrp
+pub fn decode_segwit_address(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), Bech32Error> {
+    let (decoded_hrp, data, variant) = decode_u5(address)?;
+    if decoded_hrp != hrp.to_ascii_lowercase() {
+        return Err(Bech32Error::WrongHrp);
+    }
+    let (&version, program) = data.split_first().ok_or(Bech32Error::InvalidWitness)?;
+    let program = convert_bits(program, 5, 8, false)?;
+    if check_witness(version, &program)? != variant {
+        return Err(Bech32Error::InvalidWitness);
+    }
+    Ok((version, program))
+}
+
+fn check_witness(version: u8, program: &[u8]) -> Result<Variant, Bech32Error> {
+    match (version, program.len()) {
+        (0, 20) | (0, 32) => Ok(Variant::Bech32),
+        (1..=16, 2..=40) => Ok(Variant::Bech32m),
+        _ => Err(Bech32Error::InvalidWitness),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_16_encoding;
+
+    #[test]
+    fn valid_checksums() {
+        let bech32 = [
+            "A12UEL5L",
+            "a12uel5l",
+            "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+            "?1ezyfcl",
+        ];
+        let bech32m = [
+            "A1LQFN3A",
+            "a1lqfn3a",
+            "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+            "?1v759aa",
+        ];
+        for (strings, variant) in [(bech32, Variant::Bech32), (bech32m, Variant::Bech32m)].iter() {
+            for encoded in strings.iter() {
+                let (hrp, data, decoded_variant) = decode_u5(encoded).unwrap();
+                assert_eq!(decoded_variant, *variant);
+                // encoding gives back the lowercase string
+                assert_eq!(
+                    encode_u5(&hrp, &data, *variant).unwrap(),
+                    encoded.to_ascii_lowercase()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_strings() {
+        assert_eq!(
+            decode_u5("\x201nwldj5"),
+            Err(Bech32Error::Decode(DecodeError::InvalidByte {
+                offset: 0,
+                byte: 0x20
+            }))
+        );
+        assert_eq!(
+            decode_u5("\x7f1axkwrx"),
+            Err(Bech32Error::Decode(DecodeError::InvalidByte {
+                offset: 0,
+                byte: 0x7f
+            }))
+        );
+        assert_eq!(
+            decode_u5("\u{80}1eym55h"),
+            Err(Bech32Error::Decode(DecodeError::InvalidByte {
+                offset: 0,
+                byte: 0xc2
+            }))
+        );
+        assert_eq!(
+            decode_u5("an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx"),
+            Err(Bech32Error::TooLong { len: 91 })
+        );
+        assert_eq!(
+            decode_u5("pzry9x0s0muk"),
+            Err(Bech32Error::MissingSeparator)
+        );
+        assert_eq!(decode_u5("1pzry9x0s0muk"), Err(Bech32Error::EmptyHrp));
+        assert_eq!(
+            decode_u5("x1b4n0q5v"),
+            Err(Bech32Error::Decode(DecodeError::InvalidByte {
+                offset: 2,
+                byte: b'b'
+            }))
+        );
+        assert_eq!(decode_u5("li1dgmt3"), Err(Bech32Error::TooShort));
+        assert_eq!(
+            decode_u5("de1lg7wt\u{ff}"),
+            Err(Bech32Error::Decode(DecodeError::InvalidByte {
+                offset: 8,
+                byte: 0xc3
+            }))
+        );
+        assert!(matches!(
+            decode_u5("A1G7SGD8"),
+            Err(Bech32Error::InvalidChecksum { .. })
+        ));
+        assert_eq!(decode_u5("10a06t8"), Err(Bech32Error::EmptyHrp));
+        assert_eq!(decode_u5("1qzzfhee"), Err(Bech32Error::EmptyHrp));
+    }
+
+    #[test]
+    fn mixed_case() {
+        assert_eq!(
+            decode_u5("A12uEL5L"),
+            Err(Bech32Error::MixedCase { offset: 3 })
+        );
+        assert_eq!(
+            decode_u5("a12UEL5L"),
+            Err(Bech32Error::MixedCase { offset: 3 })
+        );
+        assert_eq!(
+            encode_u5("Ab", &[], Variant::Bech32),
+            Err(Bech32Error::MixedCase { offset: 1 })
+        );
+    }
+
+    #[test]
+    fn locates_single_errors() {
+        let valid = "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw";
+        for offset in 7..valid.len() {
+            let mut corrupted = valid.as_bytes().to_vec();
+            corrupted[offset] = if corrupted[offset] == b'q' {
+                b'p'
+            } else {
+                b'q'
+            };
+            let corrupted = String::from_utf8(corrupted).unwrap();
+            assert_eq!(
+                decode_u5(&corrupted),
+                Err(Bech32Error::InvalidChecksum {
+                    error_offset: Some(offset)
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let data = (0..=255_u8).step_by(9).collect::<Vec<u8>>();
+        let encoded = encode("test", &data, Variant::Bech32m).unwrap();
+        assert_eq!(
+            decode(&encoded).unwrap(),
+            ("test".to_string(), data, Variant::Bech32m)
+        );
+        assert_eq!(convert_bits(&[0xff], 8, 5, true).unwrap(), [31, 28]);
+        assert_eq!(convert_bits(&[31, 28], 5, 8, false).unwrap(), [0xff]);
+        assert_eq!(
+            convert_bits(&[31, 29], 5, 8, false),
+            Err(Bech32Error::InvalidPadding)
+        );
+        assert_eq!(
+            convert_bits(&[32], 5, 8, false),
+            Err(Bech32Error::InvalidData)
+        );
+    }
+
+    #[test]
+    fn segwit_addresses() {
+        let program = base_16_encoding::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        assert_eq!(
+            encode_segwit_address("bc", 0, &program).unwrap(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        assert_eq!(
+            decode_segwit_address("bc", "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap(),
+            (0, program.clone())
+        );
+
+        let taproot = [program.clone(), program.clone()].concat();
+        let address = "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y";
+        assert_eq!(encode_segwit_address("bc", 1, &taproot).unwrap(), address);
+        assert_eq!(decode_segwit_address("bc", address).unwrap(), (1, taproot));
+
+        assert_eq!(
+            decode_segwit_address("tb", address),
+            Err(Bech32Error::WrongHrp)
+        );
+        // version 1 with a Bech32 checksum, and version 0 with a Bech32m one
+        let wrong_variant = encode_u5("bc", &[1, 0, 0, 0, 0], Variant::Bech32).unwrap();
+        assert_eq!(
+            decode_segwit_address("bc", &wrong_variant),
+            Err(Bech32Error::InvalidWitness)
+        );
+        assert_eq!(
+            encode_segwit_address("bc", 0, &program[..19]),
+            Err(Bech32Error::InvalidWitness)
+        );
+        assert_eq!(
+            encode_segwit_address("bc", 17, &program),
+            Err(Bech32Error::InvalidWitness)
+        );
+    }
+}
@@ -1,3 +1,4 @@
+#[allow(clippy::upper_case_acronyms)]
 pub struct CPU {
     //16 registers means hexadecimal number (0 to F) can address them
     pub register: [u8; 16],
@@ -34,7 +35,7 @@ impl CPU {
             //filter third bit by 0X00F0 and move bits to owest significant place
             let y = ((opcode & 0x00F0) >> 4) as u8;
             //filter fourth bit 0X000F and move bits to owest significant place
-            let d = ((opcode & 0x000F) >> 0) as u8;
+            let d = (opcode & 0x000F) as u8;
 
             let nnn = opcode & 0x0FFF;
             println!("nibbles c-{:?} x-{:?} y-{:?} d-{:?} ", c, x, y, d);
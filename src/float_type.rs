@@ -13,7 +13,7 @@ const RADIX: f32 = 2.0;
 //extracting the bits of those values from the container
 pub fn deconstruct_f32(n: f32) -> (u32, u32, u32) {
     //convert to a u32 so as to optimally perform bitwise manipulation on num
-    let n_: u32 = unsafe { std::mem::transmute(n) };
+    let n_: u32 = n.to_bits();
     //strip 31 unwanted bits away (since it is an f32) by shifting them into nowhere, leaving only the signed bit
     //add 31 0's at the start
     let sign = (n_ >> 31) & 1;
@@ -98,6 +98,20 @@ impl From<Q7> for f32 {
     }
 }
 
+//generating f32 that lies between 0 and 1
+#[allow(clippy::unusual_byte_groupings)]
+pub fn generate_f32(n: u8) -> f32 {
+    //underscore mark the sign, mantissa, and exponent bounderies
+    let base: u32 = 0b0_01111110_00000000000000000000000;
+    //align n to 32 bits then increase it's value by shifting 15 places left
+    let large_n = (n as u32) << 15;
+    //take a bitwise or merging the base and input value
+    let f32_bits = base | large_n;
+    //interpret f32_bits which is of type u32 as an f32
+    let m = f32::from_bits(f32_bits);
+    2.0 * (m - 0.5)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,16 +149,3 @@ mod tests {
         assert_eq!(n1, n2);
     }
 }
-
-//generating f32 that lies between 0 and 1
-pub fn generate_f32(n: u8) -> f32 {
-    //underscore mark the sign, mantissa, and exponent bounderies
-    let base: u32 = 0b0_01111110_00000000000000000000000;
-    //align n to 32 bits then increase it's value by shifting 15 places left
-    let large_n = (n as u32) << 15;
-    //take a bitwise or merging the base and input value
-    let f32_bits = base | large_n;
-    //interpret f32_bits which is of type u32 as an f32
-    let m = f32::from_bits(f32_bits);
-    2.0 * (m - 0.5)
-}
@@ -0,0 +1,236 @@
+// IMAP mailbox names (RFC 3501 section 5.1.3) are ascii, so anything else is written in a modified UTF-7:
+// printable ascii stands for itself, except '&' which is written "&-", and every run of other characters
+// is written as its UTF-16 (big endian) in Base64, between '&' and '-'. The Base64 is the IMAP_MAILBOX alphabet,
+// ',' instead of '/' and no padding. "~peter/mail/台北/日本語" is "~peter/mail/&U,BTFw-/&ZeVnLIqe-".
+// Characters outside the BMP are a surrogate pair in UTF-16, and are written as both halves.
+//
+// Every name has exactly one encoding, and the decoder only takes that one: a run of Base64 can't hold printable
+// ascii, two runs can't follow each other (they'd be one run), and the bits after the last UTF-16 unit are zero.
+use crate::base_64_encoding::{
+    decode_with_strictness, encode_using_alphabet, DecodeError, Padding, Strictness, IMAP_MAILBOX,
+};
+use std::fmt;
+
+// Offsets are in the encoded name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf7Error {
+    // a '&' without the '-' that ends its run
+    Unterminated { offset: usize },
+    // InvalidByte for a character that isn't printable ascii, or the Base64 of a run is invalid
+    Decode(DecodeError),
+    // the run at offset is an odd number of bytes, or has a surrogate without its other half
+    InvalidUtf16 { offset: usize },
+    // the run at offset isn't written the one way it should be
+    NonCanonical { offset: usize },
+}
+
+impl fmt::Display for Utf7Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Utf7Error::Unterminated { offset } => {
+                write!(f, "unterminated run at offset {}", offset)
+            }
+            Utf7Error::Decode(error) => write!(f, "{}", error),
+            Utf7Error::InvalidUtf16 { offset } => {
+                write!(f, "invalid UTF-16 in the run at offset {}", offset)
+            }
+            Utf7Error::NonCanonical { offset } => {
+                write!(f, "non-canonical run at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Utf7Error {}
+
+impl From<DecodeError> for Utf7Error {
+    fn from(error: DecodeError) -> Self {
+        Utf7Error::Decode(error)
+    }
+}
+
+fn is_direct(character: char) -> bool {
+    (' '..='~').contains(&character)
+}
+
+pub fn encode(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut run = Vec::new();
+    for character in name.chars() {
+        if is_direct(character) {
+            flush_run(&mut run, &mut out);
+            match character {
+                '&' => out.push_str("&-"),
+                character => out.push(character),
+            }
+        } else {
+            let mut units = [0; 2];
+            for unit in character.encode_utf16(&mut units) {
+                run.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+    }
+    flush_run(&mut run, &mut out);
+    out
+}
+
+fn flush_run(run: &mut Vec<u8>, out: &mut String) {
+    if !run.is_empty() {
+        out.push('&');
+        out.push_str(&encode_using_alphabet(&IMAP_MAILBOX, run));
+        out.push('-');
+        run.clear();
+    }
+}
+
+pub fn decode(encoded: &str) -> Result<String, Utf7Error> {
+    let bytes = encoded.as_bytes();
+    let mut out = String::with_capacity(encoded.len());
+    // where the last run of Base64 ended, a run starting there is non-canonical
+    let mut run_end = None;
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let byte = bytes[offset];
+        if !is_direct(byte as char) {
+            return Err(DecodeError::InvalidByte { offset, byte }.into());
+        }
+        if byte != b'&' {
+            out.push(byte as char);
+            offset += 1;
+            continue;
+        }
+
+        let end = offset
+            + encoded[offset..]
+                .find('-')
+                .ok_or(Utf7Error::Unterminated { offset })?;
+        if end == offset + 1 {
+            out.push('&');
+        } else {
+            if run_end == Some(offset) {
+                return Err(Utf7Error::NonCanonical { offset });
+            }
+            decode_run(&encoded[offset + 1..end], offset, &mut out)?;
+            run_end = Some(end + 1);
+        }
+        offset = end + 1;
+    }
+    Ok(out)
+}
+
+// the Base64 between '&' (at offset) and '-'
+fn decode_run(base64: &str, offset: usize, out: &mut String) -> Result<(), Utf7Error> {
+    // Strict turns away bits left over after the last unit that aren't zero
+    let bytes = decode_with_strictness(&IMAP_MAILBOX, base64, Padding::NoPad, Strictness::Strict)
+        .map_err(|error| error.shifted(offset + 1))?;
+    if bytes.len() % 2 != 0 {
+        return Err(Utf7Error::InvalidUtf16 { offset });
+    }
+
+    let units = bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    for character in char::decode_utf16(units) {
+        let character = character.map_err(|_| Utf7Error::InvalidUtf16 { offset })?;
+        if is_direct(character) {
+            return Err(Utf7Error::NonCanonical { offset });
+        }
+        out.push(character);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_3501_example() {
+        let name = "~peter/mail/台北/日本語";
+        assert_eq!(encode(name), "~peter/mail/&U,BTFw-/&ZeVnLIqe-");
+        assert_eq!(decode("~peter/mail/&U,BTFw-/&ZeVnLIqe-").unwrap(), name);
+    }
+
+    #[test]
+    fn ampersands_controls_and_surrogates() {
+        assert_eq!(encode("Tom & Jerry"), "Tom &- Jerry");
+        assert_eq!(encode("a\nb"), "a&AAo-b");
+        assert_eq!(encode("😀"), "&2D3eAA-");
+        assert_eq!(encode("☺☺"), "&JjomOg-");
+
+        for name in [
+            "Tom & Jerry",
+            "a\nb",
+            "😀",
+            "☺☺",
+            "&&",
+            "Entwürfe",
+            "a😀b€c",
+            "",
+        ]
+        .iter()
+        {
+            assert_eq!(decode(&encode(name)).unwrap(), *name);
+        }
+    }
+
+    #[test]
+    fn rejects_non_canonical_and_invalid_input() {
+        // 'a' written as Base64, and two runs in a row
+        assert_eq!(decode("&AGE-"), Err(Utf7Error::NonCanonical { offset: 0 }));
+        assert_eq!(
+            decode("x&Jjo-&Jjo-"),
+            Err(Utf7Error::NonCanonical { offset: 6 })
+        );
+        // bits after the last unit that aren't zero
+        assert_eq!(
+            decode("&Jjp-"),
+            Err(Utf7Error::Decode(DecodeError::InvalidLastSymbol {
+                offset: 3,
+                byte: b'p'
+            }))
+        );
+        assert_eq!(
+            decode("&Jj/-"),
+            Err(Utf7Error::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'/'
+            }))
+        );
+        assert_eq!(
+            decode("&Jj=-"),
+            Err(Utf7Error::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'='
+            }))
+        );
+
+        // whitespace inside a run is an error, not skipped
+        assert_eq!(
+            decode("&Jj o-"),
+            Err(Utf7Error::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b' '
+            }))
+        );
+        assert_eq!(
+            decode("&Jj\to-"),
+            Err(Utf7Error::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'\t'
+            }))
+        );
+
+        assert_eq!(decode("&AA-"), Err(Utf7Error::InvalidUtf16 { offset: 0 }));
+        assert_eq!(decode("a&2D0-"), Err(Utf7Error::InvalidUtf16 { offset: 1 }));
+        assert_eq!(decode("ab&Jjo"), Err(Utf7Error::Unterminated { offset: 2 }));
+        assert_eq!(
+            decode("né"),
+            Err(Utf7Error::Decode(DecodeError::InvalidByte {
+                offset: 1,
+                byte: 0xc3
+            }))
+        );
+    }
+}
@@ -0,0 +1,26 @@
+// What the streaming adapters (the Base64 and quoted-printable DecoderReader) have in common:
+// a decoding error is handed out of read() as an io::Error of kind InvalidData, and the DecodeError
+// can be taken back out of it with into_inner() and downcast().
+use crate::base_64_encoding::DecodeError;
+use std::io;
+
+pub(crate) fn invalid_data(err: DecodeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+// hands out its data a few bytes at a time, like a slow socket would
+#[cfg(test)]
+pub(crate) struct Trickle<'a> {
+    pub(crate) data: &'a [u8],
+    pub(crate) step: usize,
+}
+
+#[cfg(test)]
+impl<'a> io::Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.step.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
@@ -0,0 +1,19 @@
+pub mod armor;
+pub mod base_16_encoding;
+pub mod base_32_encoding;
+pub mod base_45_encoding;
+pub mod base_58_encoding;
+pub mod base_64_encoding;
+pub mod base_85_encoding;
+pub mod bech32;
+pub mod cpu;
+pub mod float_type;
+pub mod imap_utf7;
+mod io_util;
+pub mod mime;
+pub mod percent_encoding;
+pub mod quoted_printable;
+pub mod sha_256;
+#[cfg(test)]
+mod test_util;
+pub mod uu_encoding;
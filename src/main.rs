@@ -1,13 +1,9 @@
-mod base_64_encoding;
-mod cpu;
-mod float_type;
-
-use crate::base_64_encoding::*;
-use crate::cpu::*;
-use crate::float_type::*;
+use test_shit::base_16_encoding::HexDump;
+use test_shit::base_64_encoding::*;
+use test_shit::cpu::*;
+use test_shit::float_type::*;
 
 use std::fmt;
-use std::mem;
 
 #[derive(Debug, PartialEq, Clone)]
 enum FileState {
@@ -37,8 +33,8 @@ impl fmt::Display for File {
 }
 
 trait Read {
-    fn read(self: &Self, save_to: &mut Vec<u8>) -> Result<usize, String>;
-    fn write(self: &mut Self, data: Vec<u8>) -> Result<usize, String>;
+    fn read(&self, save_to: &mut Vec<u8>) -> Result<usize, String>;
+    fn write(&mut self, data: Vec<u8>) -> Result<usize, String>;
 }
 
 impl Read for File {
@@ -94,6 +90,7 @@ where
 fn return_closues() -> impl Fn(i32) -> i32 {
     |x| x + 4
 }
+#[allow(clippy::unusual_byte_groupings)]
 fn main() {
     let add = |x, y| x + y;
     receives_closure(add);
@@ -121,11 +118,11 @@ fn main() {
     println!("{}", &f5);
 
     let a: f32 = 42.42;
-    //transmute Reinterprets the bits of a value of one type as another type.
-    let frankentype: u32 = unsafe { std::mem::transmute(a) };
+    //to_bits reinterprets the bits of a value of one type as another type, just like a transmute would.
+    let frankentype: u32 = a.to_bits();
     println!("{}", frankentype);
     println!("{:032b}", frankentype);
-    let b: f32 = unsafe { std::mem::transmute(frankentype) };
+    let b: f32 = f32::from_bits(frankentype);
     println!("{}", b);
 
     let num: u8 = 0b1111_1111;
@@ -134,10 +131,11 @@ fn main() {
     let big_endian: [u8; 4] = [0xAA, 0xBB, 0xCC, 0xDD];
     let little_endian: [u8; 4] = [0xDD, 0xCC, 0xBB, 0xAA];
 
-    let a: i32 = unsafe { mem::transmute(big_endian) };
-    let b: i32 = unsafe { mem::transmute(little_endian) };
+    let a: i32 = i32::from_ne_bytes(big_endian);
+    let b: i32 = i32::from_ne_bytes(little_endian);
 
     println!("a {} b {}", a, b);
+    print!("{}", HexDump(&big_endian));
 
     let (signbit, exponent, fraction) = deconstruct_f32(23.908);
     let (sign, exponent, mantissa) = decode_f32_parts(signbit, exponent, fraction);
@@ -151,9 +149,9 @@ fn main() {
     println!("{:?}", f64::from(Q7::from(0.1234)));
     println!("{:?}", f64::from(Q7::from(127.)));
 
-    println!("{:032b}", (18 as u32) << 10);
+    println!("{:032b}", 18_u32 << 10);
     println!("{}", 0b0_01111110_00000000000000000000000);
-    println!("{:05b}", (00100 | 01100));
+    println!("{:05b}", (100 | 1100));
 
     println!("{}", generate_f32(200));
 
@@ -0,0 +1,718 @@
+// Two ways mail and the web put bytes into text with a label saying what they are.
+//
+// A data URL (RFC 2397) carries a whole file in a URL: data:[<media type>][;base64],<data>
+// for example data:image/png;base64,iVBORw0KGgo... Without ;base64 the data is percent-encoded.
+// Without a media type it is text/plain;charset=US-ASCII, and ";charset=x" alone means text/plain as well.
+//
+// An encoded-word (RFC 2047) carries non-ascii text in a mail header, where only ascii is allowed:
+// =?charset?encoding?text?= like =?UTF-8?B?wqFIb2xhIQ==?= (B: Base64) or =?UTF-8?Q?=C2=A1Hola!?=.
+// Q is quoted-printable made safe for headers: '_' is a space and everything that could end the word is =XX.
+// A word is at most 75 characters, longer text is split into several words separated by whitespace,
+// which decoding drops between words.
+use crate::base_16_encoding::{hex_pair, push_escape};
+use crate::base_64_encoding::{
+    decode_with_padding, decode_with_strictness, encode, Classic, DecodeError, Padding, Strictness,
+};
+use crate::percent_encoding::{self, EncodeSet, Malformed, PercentDecodeError};
+use std::fmt;
+
+const MAX_WORD_LEN: usize = 75;
+
+// the unreserved characters of RFC 3986 and the ones that mean nothing special in the data part
+const DATA: EncodeSet = EncodeSet::keeping(b"-._~!$&'()*+,;=:@/");
+// a parameter value or charset label can't hold the ',' that ends the media type,
+// the ';' between the parameters or the '=' after a name
+const PARAMETER: EncodeSet = DATA.add(b',').add(b';').add(b'=');
+
+// Charset labels are case-insensitive and have aliases, the ones we can turn into a String are their own variant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Charset {
+    UsAscii,
+    Utf8,
+    Iso8859_1,
+    Other(String),
+}
+
+impl Charset {
+    pub fn from_label(label: &str) -> Charset {
+        match label.to_ascii_lowercase().as_str() {
+            "us-ascii" | "ascii" | "us" => Charset::UsAscii,
+            "utf-8" | "utf8" => Charset::Utf8,
+            "iso-8859-1" | "iso_8859-1" | "latin1" | "l1" => Charset::Iso8859_1,
+            _ => Charset::Other(label.to_string()),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            Charset::UsAscii => "US-ASCII",
+            Charset::Utf8 => "UTF-8",
+            Charset::Iso8859_1 => "ISO-8859-1",
+            Charset::Other(label) => label,
+        }
+    }
+
+    // None for Other charsets, and for bytes that aren't valid in the charset
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Charset::UsAscii if bytes.is_ascii() => String::from_utf8(bytes.to_vec()).ok(),
+            Charset::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Charset::Iso8859_1 => Some(bytes.iter().map(|&byte| byte as char).collect()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+// type/subtype;charset=...;name=value, the type, subtype and parameter names are kept lowercase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    pub top_level: String,
+    pub subtype: String,
+    pub charset: Option<Charset>,
+    // the parameters other than charset, in order
+    pub parameters: Vec<(String, String)>,
+}
+
+impl MediaType {
+    pub fn new(top_level: &str, subtype: &str) -> Self {
+        MediaType {
+            top_level: top_level.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            charset: None,
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    pub fn parameter(mut self, name: &str, value: &str) -> Self {
+        self.parameters
+            .push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    // "image/png" without the parameters
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.top_level, self.subtype)
+    }
+
+    // the default of a data URL without a media type
+    fn text_plain() -> Self {
+        MediaType::new("text", "plain").charset(Charset::UsAscii)
+    }
+
+    // an empty type/subtype is text/plain, so ";charset=utf-8" works as RFC 2397 says
+    fn parse(text: &str) -> Result<Self, DataUrlError> {
+        let mut parts = text.split(';');
+        let essence = parts.next().unwrap_or("");
+        let mut media_type = if essence.is_empty() {
+            MediaType::new("text", "plain")
+        } else {
+            let (top_level, subtype) = essence
+                .split_once('/')
+                .ok_or(DataUrlError::InvalidMediaType)?;
+            if !is_token(top_level) || !is_token(subtype) {
+                return Err(DataUrlError::InvalidMediaType);
+            }
+            MediaType::new(top_level, subtype)
+        };
+
+        for parameter in parts {
+            let (name, value) = parameter
+                .split_once('=')
+                .ok_or(DataUrlError::InvalidMediaType)?;
+            if !is_token(name) {
+                return Err(DataUrlError::InvalidMediaType);
+            }
+            // the quotes come off before decoding, an escaped '"' is part of the value
+            let value =
+                percent_encoding::decode(value.trim_matches('"'), Malformed::Reject)?.into_owned();
+            let value = String::from_utf8(value).map_err(|_| DataUrlError::InvalidMediaType)?;
+            if name.eq_ignore_ascii_case("charset") {
+                media_type.charset = Some(Charset::from_label(&value));
+            } else {
+                media_type = media_type.parameter(name, &value);
+            }
+        }
+
+        if essence.is_empty() && media_type.charset.is_none() {
+            media_type.charset = Some(Charset::UsAscii);
+        }
+        Ok(media_type)
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.essence())?;
+        if let Some(charset) = &self.charset {
+            write!(
+                f,
+                ";charset={}",
+                percent_encoding::encode(charset.label(), &PARAMETER)
+            )?;
+        }
+        for (name, value) in &self.parameters {
+            write!(
+                f,
+                ";{}={}",
+                name,
+                percent_encoding::encode(value, &PARAMETER)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataUrl {
+    pub media_type: MediaType,
+    // whether the data is (or is to be) written in Base64 rather than percent-encoded
+    pub base64: bool,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataUrlError {
+    // doesn't start with "data:"
+    MissingScheme,
+    // no ',' between the media type and the data
+    MissingComma,
+    InvalidMediaType,
+    // a '%' without two hex digits after it, offset is in the text it was found in
+    PercentDecode(PercentDecodeError),
+    Decode(DecodeError),
+}
+
+impl From<PercentDecodeError> for DataUrlError {
+    fn from(error: PercentDecodeError) -> Self {
+        DataUrlError::PercentDecode(error)
+    }
+}
+
+impl From<DecodeError> for DataUrlError {
+    fn from(error: DecodeError) -> Self {
+        DataUrlError::Decode(error)
+    }
+}
+
+impl fmt::Display for DataUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataUrlError::MissingScheme => write!(f, "not a data: URL"),
+            DataUrlError::MissingComma => write!(f, "missing ',' before the data"),
+            DataUrlError::InvalidMediaType => write!(f, "invalid media type"),
+            DataUrlError::PercentDecode(error) => write!(f, "{}", error),
+            DataUrlError::Decode(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for DataUrlError {}
+
+pub fn encode_data_url(url: &DataUrl) -> String {
+    if url.base64 {
+        format!("data:{};base64,{}", url.media_type, encode(&url.data))
+    } else {
+        format!(
+            "data:{},{}",
+            url.media_type,
+            percent_encoding::encode_bytes(&url.data, &DATA)
+        )
+    }
+}
+
+// The Base64 is read the forgiving way browsers do: whitespace anywhere and padding optional
+pub fn decode_data_url(url: &str) -> Result<DataUrl, DataUrlError> {
+    let scheme = url
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"));
+    let rest = &url[scheme.ok_or(DataUrlError::MissingScheme)?.len()..];
+    let (header, body) = rest.split_once(',').ok_or(DataUrlError::MissingComma)?;
+    let header = header.trim();
+
+    // ";base64" is the last parameter, and has no value
+    let (header, base64) = match header
+        .len()
+        .checked_sub(7)
+        .filter(|&at| header.is_char_boundary(at))
+    {
+        Some(at) if header[at..].eq_ignore_ascii_case(";base64") => (&header[..at], true),
+        _ => (header, false),
+    };
+    let media_type = if header.is_empty() {
+        MediaType::text_plain()
+    } else {
+        MediaType::parse(header)?
+    };
+
+    let mut data = percent_encoding::decode(body, Malformed::Reject)?.into_owned();
+    if base64 {
+        let text = String::from_utf8(data).map_err(|error| {
+            let offset = error.utf8_error().valid_up_to();
+            DecodeError::InvalidByte {
+                offset,
+                byte: error.as_bytes()[offset],
+            }
+        })?;
+        data = decode_with_strictness(Classic, &text, Padding::Indifferent, Strictness::Forgiving)?;
+    }
+    Ok(DataUrl {
+        media_type,
+        base64,
+        data,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    // Base64
+    B,
+    // quoted-printable for headers
+    Q,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedWord {
+    pub charset: Charset,
+    // RFC 2231 allows a language after the charset: =?UTF-8*en?Q?...?=
+    pub language: Option<String>,
+    pub encoding: Encoding,
+    pub data: Vec<u8>,
+}
+
+// Offsets are in the encoded text of the word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodedWordError {
+    // not =?charset?encoding?text?=
+    InvalidSyntax,
+    UnknownEncoding(char),
+    // also InvalidByte for a Q escape without two hex digits, or a character Q can't hold
+    Decode(DecodeError),
+    // the text can't be turned into a String: an Other charset, or bytes not valid in it
+    UnsupportedCharset(Charset),
+}
+
+impl From<DecodeError> for EncodedWordError {
+    fn from(error: DecodeError) -> Self {
+        EncodedWordError::Decode(error)
+    }
+}
+
+impl fmt::Display for EncodedWordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodedWordError::InvalidSyntax => write!(f, "not an encoded-word"),
+            EncodedWordError::UnknownEncoding(encoding) => {
+                write!(f, "unknown encoding {:?}", encoding)
+            }
+            EncodedWordError::Decode(error) => write!(f, "{}", error),
+            EncodedWordError::UnsupportedCharset(charset) => {
+                write!(f, "can't decode {} text", charset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncodedWordError {}
+
+// One word, however long it gets
+pub fn encode_word(charset: &Charset, encoding: Encoding, data: &[u8]) -> String {
+    let text = match encoding {
+        Encoding::B => encode(data),
+        Encoding::Q => q_encode(data),
+    };
+    format!("=?{}?{}?{}?=", charset, encoding_letter(encoding), text)
+}
+
+// Text for a header as UTF-8 words of at most 75 characters, split between characters
+// and separated by a space. Folding the header into lines is left to the caller.
+pub fn encode_header(text: &str, encoding: Encoding) -> String {
+    let overhead = encode_word(&Charset::Utf8, encoding, b"").len();
+    let mut words = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        // the longest run of characters that fits, at least one of them
+        let mut end = start;
+        for (index, character) in text[start..].char_indices() {
+            let next = start + index + character.len_utf8();
+            if end > start
+                && overhead + encoded_len(encoding, &text.as_bytes()[start..next]) > MAX_WORD_LEN
+            {
+                break;
+            }
+            end = next;
+        }
+        words.push(encode_word(
+            &Charset::Utf8,
+            encoding,
+            &text.as_bytes()[start..end],
+        ));
+        start = end;
+    }
+    words.join(" ")
+}
+
+pub fn decode_word(word: &str) -> Result<EncodedWord, EncodedWordError> {
+    let inner = word
+        .strip_prefix("=?")
+        .and_then(|word| word.strip_suffix("?="))
+        .ok_or(EncodedWordError::InvalidSyntax)?;
+    let mut parts = inner.splitn(3, '?');
+    let (charset, encoding, text) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(charset), Some(encoding), Some(text))
+            if !charset.is_empty() && !encoding.is_empty() && !text.contains('?') =>
+        {
+            (charset, encoding, text)
+        }
+        _ => return Err(EncodedWordError::InvalidSyntax),
+    };
+    if text.contains(|character: char| character.is_ascii_whitespace()) {
+        return Err(EncodedWordError::InvalidSyntax);
+    }
+
+    let (charset, language) = match charset.split_once('*') {
+        Some((charset, language)) => (charset, Some(language.to_string())),
+        None => (charset, None),
+    };
+    let (encoding, data) = match encoding {
+        "B" | "b" => (
+            Encoding::B,
+            decode_with_padding(Classic, text, Padding::Indifferent)?,
+        ),
+        "Q" | "q" => (Encoding::Q, q_decode(text)?),
+        _ => {
+            return Err(EncodedWordError::UnknownEncoding(
+                encoding.chars().next().unwrap_or('?'),
+            ))
+        }
+    };
+    Ok(EncodedWord {
+        charset: Charset::from_label(charset),
+        language,
+        encoding,
+        data,
+    })
+}
+
+// Decodes the encoded-words in a header value and leaves the rest as it is.
+// Whitespace between two words is dropped, and the bytes of neighbouring words in the same charset
+// are put together before decoding, in case a character got split between them.
+// Something that looks like a word but isn't one stays as text.
+pub fn decode_header(value: &str) -> Result<String, EncodedWordError> {
+    let mut out = String::new();
+    // the charset and bytes of the words waiting to be decoded, and the whitespace after them
+    let mut pending: Option<(Charset, Vec<u8>)> = None;
+    let mut gap = "";
+
+    let mut rest = value;
+    while !rest.is_empty() {
+        // a run of whitespace, or of anything else
+        let starts_with_space = rest.starts_with(|character: char| character.is_ascii_whitespace());
+        let token_len = rest
+            .find(|character: char| character.is_ascii_whitespace() != starts_with_space)
+            .unwrap_or(rest.len());
+        let (token, after) = rest.split_at(token_len);
+        rest = after;
+
+        if starts_with_space {
+            if pending.is_some() {
+                gap = token;
+            } else {
+                out.push_str(token);
+            }
+            continue;
+        }
+
+        match decode_word(token) {
+            Ok(word) => {
+                match &mut pending {
+                    Some((charset, bytes)) if *charset == word.charset => bytes.extend(word.data),
+                    _ => {
+                        flush(&mut out, pending.take())?;
+                        pending = Some((word.charset, word.data));
+                    }
+                }
+                gap = "";
+            }
+            Err(_) => {
+                flush(&mut out, pending.take())?;
+                out.push_str(gap);
+                out.push_str(token);
+                gap = "";
+            }
+        }
+    }
+    flush(&mut out, pending)?;
+    out.push_str(gap);
+    Ok(out)
+}
+
+fn flush(out: &mut String, pending: Option<(Charset, Vec<u8>)>) -> Result<(), EncodedWordError> {
+    if let Some((charset, bytes)) = pending {
+        let text = charset
+            .decode(&bytes)
+            .ok_or(EncodedWordError::UnsupportedCharset(charset))?;
+        out.push_str(&text);
+    }
+    Ok(())
+}
+
+fn encoding_letter(encoding: Encoding) -> char {
+    match encoding {
+        Encoding::B => 'B',
+        Encoding::Q => 'Q',
+    }
+}
+
+fn encoded_len(encoding: Encoding, data: &[u8]) -> usize {
+    match encoding {
+        Encoding::B => data.len().div_ceil(3) * 4,
+        Encoding::Q => data
+            .iter()
+            .map(|&byte| {
+                if is_q_literal(byte) || byte == b' ' {
+                    1
+                } else {
+                    3
+                }
+            })
+            .sum(),
+    }
+}
+
+// the characters RFC 2047 allows as they are in any header position
+fn is_q_literal(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!*+-/".contains(&byte)
+}
+
+fn q_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len());
+    for &byte in data {
+        match byte {
+            b' ' => out.push('_'),
+            byte if is_q_literal(byte) => out.push(byte as char),
+            byte => push_escape(&mut out, b'=', byte),
+        }
+    }
+    out
+}
+
+fn q_decode(text: &str) -> Result<Vec<u8>, EncodedWordError> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut offset = 0;
+    while offset < bytes.len() {
+        match bytes[offset] {
+            b'_' => out.push(b' '),
+            b'=' => {
+                let byte = hex_pair(bytes, offset + 1)
+                    .ok_or(DecodeError::InvalidByte { offset, byte: b'=' })?;
+                out.push(byte);
+                offset += 2;
+            }
+            byte if byte.is_ascii_graphic() => out.push(byte),
+            byte => return Err(DecodeError::InvalidByte { offset, byte }.into()),
+        }
+        offset += 1;
+    }
+    Ok(out)
+}
+
+fn is_token(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_urls() {
+        let png = DataUrl {
+            media_type: MediaType::new("image", "png"),
+            base64: true,
+            data: vec![0x89, b'P', b'N', b'G'],
+        };
+        assert_eq!(encode_data_url(&png), "data:image/png;base64,iVBORw==");
+        assert_eq!(
+            decode_data_url("DATA:Image/PNG;BASE64,iVBO Rw").unwrap(),
+            png
+        );
+
+        let text = DataUrl {
+            media_type: MediaType::new("text", "html")
+                .charset(Charset::Utf8)
+                .parameter("Name", "a b.html"),
+            base64: false,
+            data: "<p>¡hola!</p>".as_bytes().to_vec(),
+        };
+        let encoded = encode_data_url(&text);
+        assert_eq!(
+            encoded,
+            "data:text/html;charset=UTF-8;name=a%20b.html,%3Cp%3E%C2%A1hola!%3C/p%3E"
+        );
+        assert_eq!(decode_data_url(&encoded).unwrap(), text);
+    }
+
+    #[test]
+    fn data_url_parameters_round_trip() {
+        // values that would end the media type, start another parameter or turn on Base64 if left as they are
+        for value in ["a,b", "a;b=c", "x;base64", "\"quoted\""].iter() {
+            let url = DataUrl {
+                media_type: MediaType::new("text", "plain").parameter("name", value),
+                base64: false,
+                data: b"hi".to_vec(),
+            };
+            assert_eq!(decode_data_url(&encode_data_url(&url)).unwrap(), url);
+        }
+        assert_eq!(
+            encode_data_url(&DataUrl {
+                media_type: MediaType::new("text", "plain").parameter("name", "x;base64"),
+                base64: false,
+                data: b"hi".to_vec(),
+            }),
+            "data:text/plain;name=x%3Bbase64,hi"
+        );
+
+        let url = DataUrl {
+            media_type: MediaType::new("text", "plain").charset(Charset::Other("a,b".to_string())),
+            base64: true,
+            data: b"hi".to_vec(),
+        };
+        let encoded = encode_data_url(&url);
+        assert_eq!(encoded, "data:text/plain;charset=a%2Cb;base64,aGk=");
+        assert_eq!(decode_data_url(&encoded).unwrap(), url);
+    }
+
+    #[test]
+    fn data_url_defaults_and_errors() {
+        let url = decode_data_url("data:,A%20brief%20note").unwrap();
+        assert_eq!(
+            url.media_type,
+            MediaType::new("text", "plain").charset(Charset::UsAscii)
+        );
+        assert_eq!(url.data, b"A brief note");
+
+        let url = decode_data_url("data:;charset=iso-8859-1,%E9").unwrap();
+        assert_eq!(url.media_type.essence(), "text/plain");
+        assert_eq!(url.media_type.charset, Some(Charset::Iso8859_1));
+        assert_eq!(
+            url.media_type.charset.unwrap().decode(&url.data).unwrap(),
+            "é"
+        );
+
+        assert_eq!(
+            decode_data_url("http://example.com"),
+            Err(DataUrlError::MissingScheme)
+        );
+        assert_eq!(
+            decode_data_url("data:text/plain"),
+            Err(DataUrlError::MissingComma)
+        );
+        assert_eq!(
+            decode_data_url("data:text,a"),
+            Err(DataUrlError::InvalidMediaType)
+        );
+        assert_eq!(
+            decode_data_url("data:,100%"),
+            Err(DataUrlError::PercentDecode(
+                PercentDecodeError::InvalidEscape { offset: 3 }
+            ))
+        );
+        assert_eq!(
+            decode_data_url("data:;base64,aGk*"),
+            Err(DataUrlError::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'*'
+            }))
+        );
+    }
+
+    #[test]
+    fn encoded_words() {
+        assert_eq!(
+            encode_word(&Charset::Utf8, Encoding::B, "¡Hola!".as_bytes()),
+            "=?UTF-8?B?wqFIb2xhIQ==?="
+        );
+        assert_eq!(
+            encode_word(&Charset::Utf8, Encoding::Q, "¡Hola, señor!".as_bytes()),
+            "=?UTF-8?Q?=C2=A1Hola=2C_se=C3=B1or!?="
+        );
+
+        let word = decode_word("=?iso-8859-1*fr?q?caf=E9_cr=e8me?=").unwrap();
+        assert_eq!(
+            word,
+            EncodedWord {
+                charset: Charset::Iso8859_1,
+                language: Some("fr".to_string()),
+                encoding: Encoding::Q,
+                data: b"caf\xe9 cr\xe8me".to_vec(),
+            }
+        );
+        assert_eq!(
+            decode_word("=?UTF-8?X?abc?="),
+            Err(EncodedWordError::UnknownEncoding('X'))
+        );
+        assert_eq!(
+            decode_word("=?UTF-8??abc?="),
+            Err(EncodedWordError::InvalidSyntax)
+        );
+        assert_eq!(
+            decode_word("=?UTF-8?Q?a b?="),
+            Err(EncodedWordError::InvalidSyntax)
+        );
+        assert_eq!(
+            decode_word("=?UTF-8?Q?a=4?="),
+            Err(EncodedWordError::Decode(DecodeError::InvalidByte {
+                offset: 1,
+                byte: b'='
+            }))
+        );
+        assert_eq!(
+            decode_word("=?UTF-8?B?wqF*?="),
+            Err(EncodedWordError::Decode(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'*'
+            }))
+        );
+    }
+
+    #[test]
+    fn header_values() {
+        assert_eq!(
+            decode_header("Re: =?UTF-8?Q?caf=C3=A9?= =?ISO-8859-1?Q?cr=E8me?= and =?x?y").unwrap(),
+            "Re: cafécrème and =?x?y"
+        );
+        // a character split between two words
+        assert_eq!(
+            decode_header("=?UTF-8?B?wg==?=\r\n =?UTF-8?B?oQ==?= !").unwrap(),
+            "¡ !"
+        );
+        assert_eq!(
+            decode_header("=?KOI8-R?B?wg==?="),
+            Err(EncodedWordError::UnsupportedCharset(Charset::Other(
+                "KOI8-R".to_string()
+            )))
+        );
+
+        let text = "Ünïcödé ".repeat(12);
+        for &encoding in [Encoding::B, Encoding::Q].iter() {
+            let encoded = encode_header(&text, encoding);
+            assert!(encoded.split(' ').all(|word| word.len() <= MAX_WORD_LEN));
+            assert_eq!(decode_header(&encoded).unwrap(), text);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+// Percent-encoding (RFC 3986) writes a byte a URL can't hold as it is as '%' and two hex digits: a space is %20.
+// Which characters can stay depends on the part of the URL: '/' separates path segments so it has to be
+// escaped inside one, but means nothing in a query, while '&' and '=' split a query into parameters.
+// An EncodeSet says which ascii characters get escaped, everything that isn't ascii always is (as its UTF-8 bytes),
+// and so are the control characters and '%' itself.
+//
+// Most input has nothing to escape, so encoding and decoding hand back the input itself when they can.
+use crate::base_16_encoding::{hex_pair, push_escape};
+use std::borrow::Cow;
+use std::fmt;
+
+// One bit per ascii character, set when the character is escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeSet(u128);
+
+// The controls and '%' are in every set: a '%' left as it is would make an escape out of what follows it,
+// and a decoded control character is never what the input held. remove() leaves them alone.
+const ALWAYS_ESCAPED: u128 = ((1 << 32) - 1) | (1 << 0x7f) | (1 << b'%');
+
+impl EncodeSet {
+    // the controls, space and '%': what no part of a URL can hold
+    pub const fn new() -> Self {
+        EncodeSet(ALWAYS_ESCAPED).add(b' ')
+    }
+
+    // everything but letters, digits and the given characters, the usual way to write down a set
+    pub const fn keeping(keep: &[u8]) -> Self {
+        let mut set = EncodeSet(u128::MAX);
+        let mut byte = 0;
+        while byte < 128 {
+            if (byte as u8).is_ascii_alphanumeric() {
+                set = set.remove(byte as u8);
+            }
+            byte += 1;
+        }
+        let mut index = 0;
+        while index < keep.len() {
+            set = set.remove(keep[index]);
+            index += 1;
+        }
+        set
+    }
+
+    pub const fn add(self, byte: u8) -> Self {
+        if byte < 128 {
+            EncodeSet(self.0 | 1 << byte)
+        } else {
+            self
+        }
+    }
+
+    pub const fn remove(self, byte: u8) -> Self {
+        if byte < 128 {
+            EncodeSet(self.0 & !(1 << byte) | ALWAYS_ESCAPED)
+        } else {
+            self
+        }
+    }
+
+    pub const fn contains(&self, byte: u8) -> bool {
+        byte >= 128 || self.0 >> byte & 1 == 1
+    }
+}
+
+impl Default for EncodeSet {
+    fn default() -> Self {
+        EncodeSet::new()
+    }
+}
+
+// Everything but the unreserved characters, safe anywhere in a URL
+pub const COMPONENT: EncodeSet = EncodeSet::keeping(b"-._~");
+// A single path segment: pchar = unreserved / sub-delims / ":" / "@", so '/' is escaped
+pub const PATH_SEGMENT: EncodeSet = EncodeSet::keeping(b"-._~!$&'()*+,;=:@");
+// A whole query (or fragment): pchar, '/' and '?'
+pub const QUERY: EncodeSet = EncodeSet::keeping(b"-._~!$&'()*+,;=:@/?");
+// A name or value inside a query, where '&', '=' and '+' have a meaning of their own
+pub const QUERY_PARAM: EncodeSet = QUERY.add(b'&').add(b'=').add(b'+');
+// The user name or the password of the userinfo, ':' separates the two so it is escaped
+pub const USERINFO: EncodeSet = EncodeSet::keeping(b"-._~!$&'()*+,;=");
+
+// What the decoder does with a '%' that isn't followed by two hex digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Malformed {
+    Reject,
+    // keep it as it is, the way browsers do
+    PassThrough,
+}
+
+// Offsets are in the encoded input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentDecodeError {
+    InvalidEscape { offset: usize },
+    // the decoded bytes aren't UTF-8, offset is where the first bad sequence was written
+    InvalidUtf8 { offset: usize },
+}
+
+impl fmt::Display for PercentDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PercentDecodeError::InvalidEscape { offset } => {
+                write!(f, "invalid escape at offset {}", offset)
+            }
+            PercentDecodeError::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PercentDecodeError {}
+
+pub fn encode<'a>(input: &'a str, set: &EncodeSet) -> Cow<'a, str> {
+    encode_bytes(input.as_bytes(), set)
+}
+
+pub fn encode_bytes<'a>(input: &'a [u8], set: &EncodeSet) -> Cow<'a, str> {
+    let first = match input.iter().position(|&byte| set.contains(byte)) {
+        Some(first) => first,
+        // non-ascii is always escaped, so input with nothing to escape is ascii
+        None => {
+            return Cow::Borrowed(std::str::from_utf8(input).expect("unescaped input is ascii"))
+        }
+    };
+
+    let mut out = String::with_capacity(input.len() + 16);
+    out.extend(input[..first].iter().map(|&byte| byte as char));
+    for &byte in &input[first..] {
+        if set.contains(byte) {
+            push_escape(&mut out, b'%', byte);
+        } else {
+            out.push(byte as char);
+        }
+    }
+    Cow::Owned(out)
+}
+
+pub fn decode<'a>(
+    input: &'a str,
+    malformed: Malformed,
+) -> Result<Cow<'a, [u8]>, PercentDecodeError> {
+    let bytes = input.as_bytes();
+    let first = match bytes.iter().position(|&byte| byte == b'%') {
+        Some(first) => first,
+        None => return Ok(Cow::Borrowed(bytes)),
+    };
+
+    let mut out = bytes[..first].to_vec();
+    let mut offset = first;
+    while offset < bytes.len() {
+        match (bytes[offset], hex_pair(bytes, offset + 1)) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                offset += 3;
+            }
+            (b'%', None) if malformed == Malformed::Reject => {
+                return Err(PercentDecodeError::InvalidEscape { offset })
+            }
+            (byte, _) => {
+                out.push(byte);
+                offset += 1;
+            }
+        }
+    }
+    Ok(Cow::Owned(out))
+}
+
+// decode() for text that has to be UTF-8
+pub fn decode_utf8<'a>(
+    input: &'a str,
+    malformed: Malformed,
+) -> Result<Cow<'a, str>, PercentDecodeError> {
+    match decode(input, malformed)? {
+        Cow::Borrowed(_) => Ok(Cow::Borrowed(input)),
+        Cow::Owned(bytes) => String::from_utf8(bytes).map(Cow::Owned).map_err(|error| {
+            PercentDecodeError::InvalidUtf8 {
+                offset: input_offset(input, error.utf8_error().valid_up_to()),
+            }
+        }),
+    }
+}
+
+// where the decoded byte at index came from in the input.
+// Only valid escapes take 3 characters, whatever is passed through takes one
+fn input_offset(input: &str, index: usize) -> usize {
+    let bytes = input.as_bytes();
+    let mut offset = 0;
+    for _ in 0..index {
+        offset += match (bytes[offset], hex_pair(bytes, offset + 1)) {
+            (b'%', Some(_)) => 3,
+            _ => 1,
+        };
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn component_sets() {
+        let text = "a/b?c=d&e+f:g@h é%";
+        assert_eq!(
+            encode(text, &PATH_SEGMENT),
+            "a%2Fb%3Fc=d&e+f:g@h%20%C3%A9%25"
+        );
+        assert_eq!(encode(text, &QUERY), "a/b?c=d&e+f:g@h%20%C3%A9%25");
+        assert_eq!(
+            encode(text, &QUERY_PARAM),
+            "a/b?c%3Dd%26e%2Bf:g@h%20%C3%A9%25"
+        );
+        assert_eq!(
+            encode(text, &USERINFO),
+            "a%2Fb%3Fc=d&e+f%3Ag%40h%20%C3%A9%25"
+        );
+        assert_eq!(
+            encode(text, &COMPONENT),
+            "a%2Fb%3Fc%3Dd%26e%2Bf%3Ag%40h%20%C3%A9%25"
+        );
+        assert_eq!(
+            encode("#[]\"<>\\^`{|}", &QUERY),
+            "%23%5B%5D%22%3C%3E%5C%5E%60%7B%7C%7D"
+        );
+    }
+
+    #[test]
+    fn user_defined_sets() {
+        const SPACES_AND_SLASHES: EncodeSet = EncodeSet::new().add(b'/');
+        assert_eq!(
+            encode("a b/c?d%\n", &SPACES_AND_SLASHES),
+            "a%20b%2Fc?d%25%0A"
+        );
+        assert!(EncodeSet::keeping(b"%").contains(b'%'));
+        assert!(EncodeSet::keeping(b"\n\x7f").contains(b'\n'));
+        assert!(EncodeSet::keeping(b"\n\x7f").contains(0x7f));
+        // '%' and the controls can't be taken out, so what comes back still decodes to the input
+        let set = EncodeSet::new().remove(b'%').remove(0).remove(b' ');
+        assert!(set.contains(b'%') && set.contains(0) && !set.contains(b' '));
+        assert_eq!(encode("a%41\r", &set), "a%2541%0D");
+        assert_eq!(
+            decode_utf8(&encode("a%41\r", &set), Malformed::Reject).unwrap(),
+            "a%41\r"
+        );
+        assert!(!PATH_SEGMENT.remove(b'/').contains(b'/'));
+        assert!(COMPONENT.remove(0xe9).contains(0xe9));
+        assert_eq!(encode_bytes(&[0xff, b'a'], &EncodeSet::default()), "%FFa");
+    }
+
+    #[test]
+    fn borrows_when_nothing_changes() {
+        assert!(matches!(
+            encode("plain-text_1.0~", &COMPONENT),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(encode("a b", &COMPONENT), Cow::Owned(_)));
+        assert!(matches!(
+            decode("plain", Malformed::Reject).unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            decode_utf8("plain", Malformed::Reject).unwrap(),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(
+            decode_utf8("caf%C3%a9%20%2F", Malformed::Reject).unwrap(),
+            "café /"
+        );
+        assert_eq!(
+            decode("%FF%00", Malformed::Reject).unwrap(),
+            [0xff, 0x00].as_ref()
+        );
+
+        assert_eq!(
+            decode("100%", Malformed::Reject),
+            Err(PercentDecodeError::InvalidEscape { offset: 3 })
+        );
+        assert_eq!(
+            decode("%4g", Malformed::Reject),
+            Err(PercentDecodeError::InvalidEscape { offset: 0 })
+        );
+        assert_eq!(
+            decode_utf8("100%+%4g%41", Malformed::PassThrough).unwrap(),
+            "100%+%4gA"
+        );
+
+        assert_eq!(
+            decode_utf8("%%41%FF", Malformed::PassThrough),
+            Err(PercentDecodeError::InvalidUtf8 { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = (0..=255_u8).collect::<Vec<u8>>();
+        for set in [
+            COMPONENT,
+            PATH_SEGMENT,
+            QUERY,
+            QUERY_PARAM,
+            USERINFO,
+            EncodeSet::new(),
+        ]
+        .iter()
+        {
+            let encoded = encode_bytes(&data, set);
+            assert_eq!(
+                decode(&encoded, Malformed::Reject).unwrap(),
+                data.as_slice()
+            );
+        }
+    }
+}
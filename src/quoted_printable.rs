@@ -0,0 +1,296 @@
+// Quoted-printable (RFC 2045) is the mail encoding for text that is mostly ascii already:
+// printable characters stay as they are, anything else becomes '=' and its two hex digits (=C3=A9 for é),
+// so the text stays readable. '=' itself is written =3D.
+//
+// Lines are at most 76 characters. A longer line is broken with a soft line break, a '=' at the end of the line,
+// which the decoder removes. The line breaks of the text itself (hard line breaks) are kept as they are,
+// unless the data is binary, where CR and LF are just bytes and get escaped like the rest.
+// Mail gateways are known to strip spaces and tabs at the end of lines, so they are escaped there.
+//
+// Decoding takes both CRLF and LF lines and keeps the line endings it finds.
+// Whitespace at the end of a line was added on the way and is dropped.
+// A '=' that isn't followed by two hex digits or a line break is an error.
+use crate::base_16_encoding::{escape, hex_pair};
+use crate::base_64_encoding::{DecodeError, LineEnding};
+
+mod decoder_reader;
+mod encoder_writer;
+pub use decoder_reader::DecoderReader;
+pub use encoder_writer::EncoderWriter;
+
+// the '=' of a soft line break makes the 76th character
+const MAX_LINE_LEN: usize = 76;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // line breaks in the input are line breaks in the output
+    Text,
+    // CR and LF are escaped like any other byte
+    Binary,
+}
+
+// Encodes one byte at a time, so the one-shot functions and the writer share it.
+// A space or tab can't be written until we know whether the line ends after it, so it waits in pending_space,
+// and a CR waits to see if a LF makes a line break out of it.
+struct Encoder {
+    mode: Mode,
+    ending: LineEnding,
+    // characters on the current line
+    column: usize,
+    pending_space: Option<u8>,
+    pending_cr: bool,
+}
+
+impl Encoder {
+    fn new(mode: Mode, ending: LineEnding) -> Self {
+        Encoder {
+            mode,
+            ending,
+            column: 0,
+            pending_space: None,
+            pending_cr: false,
+        }
+    }
+
+    fn push(&mut self, byte: u8, out: &mut String) {
+        if self.pending_cr {
+            self.pending_cr = false;
+            if byte == b'\n' {
+                return self.hard_break(out);
+            }
+            self.flush_space(false, out);
+            self.put_escaped(b'\r', out);
+        }
+
+        match byte {
+            b'\r' if self.mode == Mode::Text => self.pending_cr = true,
+            b'\n' if self.mode == Mode::Text => self.hard_break(out),
+            b' ' | b'\t' => {
+                self.flush_space(false, out);
+                self.pending_space = Some(byte);
+            }
+            b'!'..=b'~' if byte != b'=' => {
+                self.flush_space(false, out);
+                self.put(&[byte], out);
+            }
+            _ => {
+                self.flush_space(false, out);
+                self.put_escaped(byte, out);
+            }
+        }
+    }
+
+    // the end of the data is the end of a line
+    fn finish(&mut self, out: &mut String) {
+        if self.pending_cr {
+            self.pending_cr = false;
+            self.flush_space(false, out);
+            self.put_escaped(b'\r', out);
+        }
+        self.flush_space(true, out);
+    }
+
+    fn hard_break(&mut self, out: &mut String) {
+        self.flush_space(true, out);
+        out.push_str(self.ending.as_str());
+        self.column = 0;
+    }
+
+    // escaped when the line ends right after it
+    fn flush_space(&mut self, escape: bool, out: &mut String) {
+        if let Some(space) = self.pending_space.take() {
+            if escape {
+                self.put_escaped(space, out);
+            } else {
+                self.put(&[space], out);
+            }
+        }
+    }
+
+    fn put_escaped(&mut self, byte: u8, out: &mut String) {
+        self.put(&escape(b'=', byte), out);
+    }
+
+    // an escape is never split by a soft line break
+    fn put(&mut self, encoded: &[u8], out: &mut String) {
+        if self.column + encoded.len() > MAX_LINE_LEN - 1 {
+            out.push('=');
+            out.push_str(self.ending.as_str());
+            self.column = 0;
+        }
+        out.extend(encoded.iter().map(|&byte| byte as char));
+        self.column += encoded.len();
+    }
+}
+
+// Text with CRLF line breaks, as mail wants them
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, Mode::Text, LineEnding::CrLf)
+}
+
+pub fn encode_with(data: &[u8], mode: Mode, ending: LineEnding) -> String {
+    let mut encoder = Encoder::new(mode, ending);
+    let mut out = String::with_capacity(data.len() + data.len() / 2);
+    for &byte in data {
+        encoder.push(byte, &mut out);
+    }
+    encoder.finish(&mut out);
+    out
+}
+
+pub fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::with_capacity(encoded.len());
+    let mut offset = 0;
+    for line in encoded.as_bytes().split_inclusive(|&byte| byte == b'\n') {
+        decode_line(line, offset, &mut out)?;
+        offset += line.len();
+    }
+    Ok(out)
+}
+
+// Decodes a line, with its line ending if it has one, starting at offset in the input.
+// The reader hands lines in one at a time, so this is where all the decoding happens.
+fn decode_line(line: &[u8], offset: usize, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+    let (body, ending) = match line {
+        [body @ .., b'\r', b'\n'] => (body, &line[line.len() - 2..]),
+        [body @ .., b'\n'] => (body, &line[line.len() - 1..]),
+        body => (body, &[][..]),
+    };
+    let body = match body.iter().rposition(|&byte| byte != b' ' && byte != b'\t') {
+        Some(last) => &body[..=last],
+        None => &[][..],
+    };
+    // a soft line break joins this line to the next one
+    let (body, ending) = match body {
+        [body @ .., b'='] => (body, &[][..]),
+        body => (body, ending),
+    };
+
+    let mut index = 0;
+    while index < body.len() {
+        if body[index] == b'=' {
+            // lowercase hex digits are not what RFC 2045 writes, but they are read all the same
+            let byte = hex_pair(body, index + 1).ok_or(DecodeError::InvalidByte {
+                offset: offset + index,
+                byte: b'=',
+            })?;
+            out.push(byte);
+            index += 3;
+        } else {
+            out.push(body[index]);
+            index += 1;
+        }
+    }
+    out.extend_from_slice(ending);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(encode("café = 2€".as_bytes()), "caf=C3=A9 =3D 2=E2=82=AC");
+        assert_eq!(
+            decode("caf=C3=A9 =3d 2=E2=82=AC").unwrap(),
+            "café = 2€".as_bytes()
+        );
+        assert_eq!(encode(b"\x00\x7f~!"), "=00=7F~!");
+    }
+
+    #[test]
+    fn line_breaks_and_trailing_whitespace() {
+        assert_eq!(
+            encode(b"one \ntwo\t\r\nthree "),
+            "one=20\r\ntwo=09\r\nthree=20"
+        );
+        assert_eq!(
+            encode_with(b"a \r\nb\rc", Mode::Text, LineEnding::Lf),
+            "a=20\nb=0Dc"
+        );
+        assert_eq!(
+            encode_with(b"a \r\n", Mode::Binary, LineEnding::CrLf),
+            "a =0D=0A"
+        );
+        // spaces in the middle of a line stay
+        assert_eq!(encode(b"a  b"), "a  b");
+    }
+
+    #[test]
+    fn soft_line_breaks() {
+        let data = "x".repeat(200);
+        let encoded = encode(data.as_bytes());
+        let lines = encoded.split("\r\n").collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_LEN));
+        assert_eq!(lines[0], format!("{}=", "x".repeat(75)));
+        assert_eq!(decode(&encoded).unwrap(), data.as_bytes());
+
+        // escapes are not split
+        let data = vec![0xff; 30];
+        let encoded = encode(&data);
+        assert_eq!(
+            encoded.lines().next().unwrap(),
+            format!("{}=", "=FF".repeat(25))
+        );
+        assert_eq!(decode(&encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn lenient_decoding() {
+        // LF and CRLF, soft breaks with whitespace after them, whitespace added at the end of lines
+        assert_eq!(decode("a=  \nb\r\nc  \nd=\r\n").unwrap(), b"ab\r\nc\nd");
+        assert_eq!(decode("a=\n").unwrap(), b"a");
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(
+            decode("ab=4"),
+            Err(DecodeError::InvalidByte {
+                offset: 2,
+                byte: b'='
+            })
+        );
+        assert_eq!(
+            decode("a\nb=G1\n"),
+            Err(DecodeError::InvalidByte {
+                offset: 3,
+                byte: b'='
+            })
+        );
+        assert_eq!(
+            decode("=\r1\n"),
+            Err(DecodeError::InvalidByte {
+                offset: 0,
+                byte: b'='
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = (0..=255_u8).cycle().take(2000).collect::<Vec<u8>>();
+        for &mode in [Mode::Text, Mode::Binary].iter() {
+            for &ending in [LineEnding::Lf, LineEnding::CrLf].iter() {
+                let encoded = encode_with(&data, mode, ending);
+                assert!(encoded
+                    .lines()
+                    .all(|line| line.trim_end_matches('\r').len() <= MAX_LINE_LEN));
+                // in text mode every LF comes back as a line break of the chosen ending
+                let expected = match mode {
+                    Mode::Binary => data.clone(),
+                    Mode::Text => data
+                        .iter()
+                        .flat_map(|&byte| match byte {
+                            b'\n' => ending.as_str().as_bytes().to_vec(),
+                            byte => vec![byte],
+                        })
+                        .collect(),
+                };
+                assert!(decode(&encoded).unwrap() == expected);
+            }
+        }
+    }
+}
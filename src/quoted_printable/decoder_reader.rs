@@ -0,0 +1,127 @@
+// A streaming decoder: reading from it pulls quoted-printable from the inner reader and hands out the decoded bytes.
+// Decoding goes a line at a time, since only the end of a line tells whether trailing whitespace
+// or a soft line break is there. Whatever comes after the last line break read so far is kept for the next read,
+// and decoded as the last line when the inner reader is exhausted.
+// Errors are returned as io::Error of kind InvalidData wrapping a DecodeError, whose offsets
+// count from the start of the stream.
+use super::decode_line;
+use crate::io_util::invalid_data;
+use std::io::{self, Read};
+
+const BUFFER_SIZE: usize = 1024;
+
+pub struct DecoderReader<R: Read> {
+    inner: R,
+    // the start of a line we haven't seen the end of yet
+    encoded: Vec<u8>,
+    // offset of encoded in the stream
+    offset: usize,
+    // decoded bytes waiting to be read, starting at decoded_pos
+    decoded: Vec<u8>,
+    decoded_pos: usize,
+    finished: bool,
+}
+
+impl<R: Read> DecoderReader<R> {
+    pub fn new(inner: R) -> Self {
+        DecoderReader {
+            inner,
+            encoded: Vec::new(),
+            offset: 0,
+            decoded: Vec::new(),
+            decoded_pos: 0,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // reads the next piece of the stream and decodes the lines it completes
+    fn fill(&mut self) -> io::Result<()> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let read = self.inner.read(&mut buffer)?;
+
+        self.decoded.clear();
+        self.decoded_pos = 0;
+        self.encoded.extend_from_slice(&buffer[..read]);
+
+        let complete = if read == 0 {
+            self.finished = true;
+            self.encoded.len()
+        } else {
+            match self.encoded.iter().rposition(|&byte| byte == b'\n') {
+                Some(last) => last + 1,
+                None => return Ok(()),
+            }
+        };
+
+        for line in self.encoded[..complete].split_inclusive(|&byte| byte == b'\n') {
+            decode_line(line, self.offset, &mut self.decoded).map_err(invalid_data)?;
+            self.offset += line.len();
+        }
+        self.encoded.drain(..complete);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecoderReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // a read that ended in the middle of a line decoded nothing, so we keep going
+        while self.decoded_pos == self.decoded.len() {
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let available = &self.decoded[self.decoded_pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.decoded_pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{decode, encode};
+    use super::*;
+    use crate::base_64_encoding::DecodeError;
+    use crate::io_util::Trickle;
+
+    #[test]
+    fn matches_one_shot_decoding() {
+        let data = (0..=255_u8).cycle().take(3000).collect::<Vec<u8>>();
+        let encoded = format!("{}=  \nsoft  \r\n", encode(&data));
+        for step in [1, 2, 3, 7, 100, 5000].iter() {
+            let mut reader = DecoderReader::new(Trickle {
+                data: encoded.as_bytes(),
+                step: *step,
+            });
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            assert_eq!(out, decode(&encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn errors_report_stream_offsets() {
+        let encoded = format!("{}\r\nab=Z", "x".repeat(2000));
+        let mut reader = DecoderReader::new(Trickle {
+            data: encoded.as_bytes(),
+            step: 300,
+        });
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = *err.into_inner().unwrap().downcast::<DecodeError>().unwrap();
+        assert_eq!(
+            err,
+            DecodeError::InvalidByte {
+                offset: 2004,
+                byte: b'='
+            }
+        );
+    }
+}
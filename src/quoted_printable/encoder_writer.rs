@@ -0,0 +1,114 @@
+// A streaming encoder: bytes written to it come out quoted-printable on the inner writer.
+// Each write is encoded as it comes, only a trailing space, tab or CR waits for the next byte
+// to know whether a line ends there. finish() or dropping the encoder writes it out.
+use super::{Encoder, Mode};
+use crate::base_64_encoding::LineEnding;
+use std::io::{self, Write};
+
+pub struct EncoderWriter<W: Write> {
+    // None once finish() handed the writer back
+    inner: Option<W>,
+    encoder: Encoder,
+}
+
+impl<W: Write> EncoderWriter<W> {
+    pub fn new(inner: W) -> Self {
+        EncoderWriter::with_mode(inner, Mode::Text)
+    }
+
+    pub fn with_mode(inner: W, mode: Mode) -> Self {
+        EncoderWriter {
+            inner: Some(inner),
+            encoder: Encoder::new(mode, LineEnding::CrLf),
+        }
+    }
+
+    pub fn with_line_ending(mut self, ending: LineEnding) -> Self {
+        self.encoder.ending = ending;
+        self
+    }
+
+    // writes what is left and returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_end()?;
+        let mut inner = self.inner.take().expect("writer is only taken by finish");
+        inner.flush()?;
+        Ok(inner)
+    }
+
+    fn write_end(&mut self) -> io::Result<()> {
+        let mut encoded = String::new();
+        self.encoder.finish(&mut encoded);
+        self.write_encoded(&encoded)
+    }
+
+    fn write_encoded(&mut self, encoded: &str) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.write_all(encoded.as_bytes()),
+            None => Err(io::Error::other("encoder already finished")),
+        }
+    }
+}
+
+impl<W: Write> Write for EncoderWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut encoded = String::with_capacity(buf.len() + buf.len() / 2);
+        for &byte in buf {
+            self.encoder.push(byte, &mut encoded);
+        }
+        self.write_encoded(&encoded)?;
+        Ok(buf.len())
+    }
+
+    // what waits for the next byte can't be written yet, so only the inner writer is flushed
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for EncoderWriter<W> {
+    fn drop(&mut self) {
+        // a held back space or CR is still written, but a failing writer goes unnoticed here,
+        // only finish() hands the error back
+        if !std::thread::panicking() && self.inner.is_some() {
+            let _ = self.write_end();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{encode, encode_with};
+    use super::*;
+
+    #[test]
+    fn matches_one_shot_encoding() {
+        let data = b"line one \r\nline two\t\n=tabs\tand spaces \r".repeat(20);
+        for piece in 1..=10 {
+            let mut writer = EncoderWriter::new(Vec::new());
+            for chunk in data.chunks(piece) {
+                writer.write_all(chunk).unwrap();
+            }
+            let out = writer.finish().unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), encode(&data));
+        }
+    }
+
+    #[test]
+    fn binary_and_drop() {
+        let mut out = Vec::new();
+        {
+            let mut writer =
+                EncoderWriter::with_mode(&mut out, Mode::Binary).with_line_ending(LineEnding::Lf);
+            writer.write_all(b"a \r\n").unwrap();
+            writer.write_all(b" ").unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            encode_with(b"a \r\n ", Mode::Binary, LineEnding::Lf)
+        );
+    }
+}
@@ -0,0 +1,25 @@
+// Fixtures shared by the tests of several codecs.
+
+// A simple linear congruential generator, so the tests that want varied data get the same data on every run
+pub(crate) struct Lcg(u32);
+
+impl Lcg {
+    pub(crate) fn new(seed: u32) -> Self {
+        Lcg(seed)
+    }
+
+    // only the high bits are handed out, the low bits of an LCG repeat with a short period
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        self.0 = self.0.wrapping_mul(1103515245).wrapping_add(12345);
+        self.0 >> 16
+    }
+
+    // a number from 0 up to, but not including, bound
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        self.next_u32() as usize % bound
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u32() as u8).collect()
+    }
+}
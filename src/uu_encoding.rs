@@ -0,0 +1,345 @@
+// uuencode ("Unix-to-Unix") is the Base64 of the days before MIME: the same 3 bytes into 4 groups of 6 bits
+// done by split(), only the 6-bit values are written as the characters 32 to 95 (space to underscore).
+// Space gets stripped by mailers, so 0 is written as a backtick instead, and read as either.
+// xxencode is the same thing with an alphabet of letters, digits, + and -, which survives EBCDIC gateways.
+//
+// The data is written in lines of up to 45 bytes, each line starting with the character for its byte count,
+// and framed by a header carrying the unix permissions (in octal) and the file name, and a trailer:
+//
+// begin 644 cat.txt
+// #0V%T
+// `
+// end
+//
+// The last group of a line is filled up with zero bytes, the count at the start tells how many are real.
+// The empty line (the count 0) before "end" marks the end of the data.
+use crate::base_64_encoding::{numbered_lines, split, stitch, Alphabet, DecodeError};
+use std::fmt;
+
+const LINE_BYTES: usize = 45;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uu;
+
+impl Alphabet for Uu {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        match index {
+            0 => Some('`'),
+            1..=63 => Some((index + 32) as char),
+            _ => None,
+        }
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        match character {
+            ' ' | '`' => Some(0),
+            '!'..='_' => Some(character as u8 - 32),
+            _ => None,
+        }
+    }
+
+    // never used, see get_optional_padding_char
+    fn get_padding_char(&self) -> char {
+        '='
+    }
+
+    fn get_optional_padding_char(&self) -> Option<char> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Xx;
+
+impl Alphabet for Xx {
+    fn get_char_for_index(&self, index: u8) -> Option<char> {
+        match index {
+            0 => Some('+'),
+            1 => Some('-'),
+            2..=11 => Some((b'0' + index - 2) as char),
+            12..=37 => Some((b'A' + index - 12) as char),
+            38..=63 => Some((b'a' + index - 38) as char),
+            _ => None,
+        }
+    }
+
+    fn get_index_for_char(&self, character: char) -> Option<u8> {
+        match character {
+            '+' => Some(0),
+            '-' => Some(1),
+            '0'..='9' => Some(character as u8 - b'0' + 2),
+            'A'..='Z' => Some(character as u8 - b'A' + 12),
+            'a'..='z' => Some(character as u8 - b'a' + 38),
+            _ => None,
+        }
+    }
+
+    // never used, see get_optional_padding_char
+    fn get_padding_char(&self) -> char {
+        '='
+    }
+
+    fn get_optional_padding_char(&self) -> Option<char> {
+        None
+    }
+}
+
+// What the header said, and the data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UuFile {
+    pub name: String,
+    pub mode: u32,
+    pub data: Vec<u8>,
+}
+
+// Lines are numbered from 1, offsets are in bytes from the start of the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UuError {
+    MissingBegin,
+    // the begin line has no valid octal mode or no name
+    InvalidHeader { line: usize },
+    // InvalidByte for a character outside of the alphabet
+    Decode(DecodeError),
+    // a line has fewer characters than its count needs, or a count above 45
+    InvalidLineLength { line: usize },
+    MissingEnd,
+}
+
+impl fmt::Display for UuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UuError::MissingBegin => write!(f, "missing begin line"),
+            UuError::InvalidHeader { line } => write!(f, "invalid begin line at line {}", line),
+            UuError::Decode(err) => err.fmt(f),
+            UuError::InvalidLineLength { line } => {
+                write!(f, "invalid line length at line {}", line)
+            }
+            UuError::MissingEnd => write!(f, "missing end line"),
+        }
+    }
+}
+
+impl std::error::Error for UuError {}
+
+impl From<DecodeError> for UuError {
+    fn from(err: DecodeError) -> Self {
+        UuError::Decode(err)
+    }
+}
+
+// mode is written in octal, only the permission bits are kept
+pub fn encode_using_alphabet<T: Alphabet>(
+    alphabet: &T,
+    name: &str,
+    mode: u32,
+    data: &[u8],
+) -> String {
+    let mut out = format!("begin {:o} {}\n", mode & 0o7777, name);
+    for line in data.chunks(LINE_BYTES).chain(std::iter::once(&[][..])) {
+        out.extend(alphabet.get_char_for_index(line.len() as u8));
+        for chunk in line.chunks(3) {
+            let mut group = [0; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            out.extend(
+                split(&group)
+                    .into_iter()
+                    .filter_map(|index| alphabet.get_char_for_index(index)),
+            );
+        }
+        out.push('\n');
+    }
+    out.push_str("end\n");
+    out
+}
+
+// Lines before begin (mail headers and the like) are skipped, as is anything after end.
+// Lines may end in CRLF, and characters past what the count needs are ignored,
+// some encoders put a checksum character there.
+pub fn decode_using_alphabet<T: Alphabet>(alphabet: &T, text: &str) -> Result<UuFile, UuError> {
+    let mut lines = numbered_lines(text);
+
+    let (name, mode) = loop {
+        let (number, _, line) = lines.next().ok_or(UuError::MissingBegin)?;
+        if let Some(header) = line.strip_prefix("begin ") {
+            break parse_header(header).ok_or(UuError::InvalidHeader { line: number })?;
+        }
+    };
+
+    let mut data = Vec::new();
+    loop {
+        let (number, offset, line) = lines.next().ok_or(UuError::MissingEnd)?;
+        if line == "end" {
+            break;
+        }
+        let mut characters = line
+            .char_indices()
+            .map(|(index, character)| (offset + index, character));
+        let count = match characters.next() {
+            Some((offset, character)) => index_for(alphabet, offset, character)? as usize,
+            // a blank line is taken as the count 0, when the space was stripped
+            None => 0,
+        };
+        if count > LINE_BYTES {
+            return Err(UuError::InvalidLineLength { line: number });
+        }
+
+        let needed = count.div_ceil(3) * 4;
+        let mut indices = Vec::with_capacity(needed);
+        for (offset, character) in characters.take(needed) {
+            indices.push(index_for(alphabet, offset, character)?);
+        }
+        if indices.len() < needed {
+            return Err(UuError::InvalidLineLength { line: number });
+        }
+        let mut bytes = indices
+            .chunks(4)
+            .flat_map(|group| stitch(group.to_vec()))
+            .collect::<Vec<u8>>();
+        bytes.truncate(count);
+        data.extend(bytes);
+
+        // the count 0 line ends the data, end has to come right after it
+        if count == 0 {
+            let (_, _, line) = lines.next().ok_or(UuError::MissingEnd)?;
+            if line != "end" {
+                return Err(UuError::MissingEnd);
+            }
+            break;
+        }
+    }
+
+    Ok(UuFile { name, mode, data })
+}
+
+pub fn encode(name: &str, mode: u32, data: &[u8]) -> String {
+    encode_using_alphabet(&Uu, name, mode, data)
+}
+
+pub fn decode(text: &str) -> Result<UuFile, UuError> {
+    decode_using_alphabet(&Uu, text)
+}
+
+pub fn encode_xx(name: &str, mode: u32, data: &[u8]) -> String {
+    encode_using_alphabet(&Xx, name, mode, data)
+}
+
+pub fn decode_xx(text: &str) -> Result<UuFile, UuError> {
+    decode_using_alphabet(&Xx, text)
+}
+
+// "644 cat.txt", the name can have spaces in it
+fn parse_header(header: &str) -> Option<(String, u32)> {
+    let mut parts = header.trim_start().splitn(2, ' ');
+    let mode = u32::from_str_radix(parts.next()?, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)?;
+    let name = parts.next()?;
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), mode))
+}
+
+fn index_for<T: Alphabet>(alphabet: &T, offset: usize, character: char) -> Result<u8, UuError> {
+    alphabet
+        .get_index_for_char(character)
+        .ok_or(UuError::Decode(DecodeError::InvalidByte {
+            offset,
+            byte: character.to_string().as_bytes()[0],
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uuencode() {
+        assert_eq!(
+            encode("cat.txt", 0o644, b"Cat"),
+            "begin 644 cat.txt\n#0V%T\n`\nend\n"
+        );
+        assert_eq!(encode("empty", 0o600, b""), "begin 600 empty\n`\nend\n");
+
+        let data = (0..50).collect::<Vec<u8>>();
+        let encoded = encode("bytes.bin", 0o755, &data);
+        assert_eq!(
+            encoded,
+            "begin 755 bytes.bin\n\
+             M``$\"`P0%!@<(\"0H+#`T.#Q`1$A,4%187&!D:&QP='A\\@(2(C)\"4F)R@I*BLL\n\
+             %+2XO,#$`\n\
+             `\n\
+             end\n"
+        );
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(
+            decoded,
+            UuFile {
+                name: "bytes.bin".to_string(),
+                mode: 0o755,
+                data
+            }
+        );
+    }
+
+    #[test]
+    fn xxencode() {
+        let encoded = encode_xx("cat.txt", 0o644, b"Cat");
+        assert_eq!(encoded, "begin 644 cat.txt\n1Eq3o\n+\nend\n");
+        assert_eq!(decode_xx(&encoded).unwrap().data, b"Cat");
+
+        for len in 0..100 {
+            let data = (0..len as u8)
+                .map(|byte| byte.wrapping_mul(37))
+                .collect::<Vec<u8>>();
+            assert_eq!(decode_xx(&encode_xx("f", 0o644, &data)).unwrap().data, data);
+        }
+    }
+
+    #[test]
+    fn lenient_decoding() {
+        // mail headers before begin, CRLF, spaces for zero, a name with a space and a checksum character
+        let text = "From: someone\r\n\r\nbegin 644 my cat.txt\r\n#0V%T!\r\n \r\nend\r\n";
+        let decoded = decode(text).unwrap();
+        assert_eq!(
+            decoded,
+            UuFile {
+                name: "my cat.txt".to_string(),
+                mode: 0o644,
+                data: b"Cat".to_vec()
+            }
+        );
+        // without the count 0 line
+        assert_eq!(decode("begin 644 a\n#0V%T\nend").unwrap().data, b"Cat");
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode("#0V%T\n`\nend\n"), Err(UuError::MissingBegin));
+        assert_eq!(
+            decode("begin 844 a\n`\nend\n"),
+            Err(UuError::InvalidHeader { line: 1 })
+        );
+        assert_eq!(
+            decode("begin 644\n`\nend\n"),
+            Err(UuError::InvalidHeader { line: 1 })
+        );
+        assert_eq!(
+            decode("begin 644 a\n#0V%t\n`\nend\n"),
+            Err(UuError::Decode(DecodeError::InvalidByte {
+                offset: 16,
+                byte: b't'
+            }))
+        );
+        assert_eq!(
+            decode("begin 644 a\n#0V%\n`\nend\n"),
+            Err(UuError::InvalidLineLength { line: 2 })
+        );
+        assert_eq!(
+            decode("begin 644 a\nN\n`\nend\n"),
+            Err(UuError::InvalidLineLength { line: 2 })
+        );
+        assert_eq!(decode("begin 644 a\n#0V%T\n"), Err(UuError::MissingEnd));
+        assert_eq!(decode("begin 644 a\n#0V%T\n`\n"), Err(UuError::MissingEnd));
+    }
+}